@@ -28,117 +28,251 @@ impl<T> MapAt for Vec<Vec<T>> {
     }
 }
 
-trait Regions {
-    fn regions(&self) -> Vec<HashSet<IVec2>>;
+const DIRS: [IVec2; 4] = [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y];
+const CORNERS: [IVec2; 4] = [IVec2::ONE, IVec2::NEG_ONE, ivec2(1, -1), ivec2(-1, 1)];
+
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub label: usize,
+    pub plant: u8,
+    pub area: i32,
+    pub perimeter: i32,
+    pub sides: i32,
+    /// Minimum and maximum corners, inclusive.
+    pub bbox: (IVec2, IVec2),
+    pub holes: i32,
+    /// Labels of the regions inside the holes.
+    pub enclosed: Vec<usize>,
+}
+
+impl Region {
+    pub fn new(label: usize, plant: u8, pos: IVec2) -> Self {
+        Self {
+            label,
+            plant,
+            area: 0,
+            perimeter: 0,
+            sides: 0,
+            bbox: (pos, pos),
+            holes: 0,
+            enclosed: Vec::new(),
+        }
+    }
+
+    pub fn price(&self) -> i32 {
+        self.area * self.perimeter
+    }
+
+    pub fn discounted_price(&self) -> i32 {
+        self.area * self.sides
+    }
 }
 
-impl Regions for &[&[u8]] {
-    fn regions(&self) -> Vec<HashSet<IVec2>> {
-        self.iter()
-            .enumerate()
-            .flat_map(|(y, row)| {
-                row.iter()
-                    .enumerate()
-                    .map(move |(x, &plant)| (ivec2(x as i32, y as i32), plant))
-            })
+#[derive(Debug, Clone)]
+pub struct Garden {
+    pub size: IVec2,
+    /// Region label of each cell, row by row.
+    pub labels: Vec<usize>,
+    pub regions: Vec<Region>,
+}
+
+impl Garden {
+    pub fn new(map: &[&[u8]]) -> Self {
+        let size = ivec2(
+            map.first().map_or(0, |row| row.len()) as i32,
+            map.len() as i32,
+        );
+        let index = |pos: IVec2| (pos.y * size.x + pos.x) as usize;
+
+        let (labels, regions) = (0..size.y)
+            .cartesian_product(0..size.x)
+            .map(|(y, x)| ivec2(x, y))
             .fold(
-                (Vec::new(), HashSet::new()),
-                |(mut regions, mut visited), (pos, plant)| {
-                    if visited.contains(&pos) {
-                        return (regions, visited);
+                (vec![None; (size.x * size.y) as usize], Vec::new()),
+                |(mut labels, mut regions), pos| {
+                    if labels[index(pos)].is_some() {
+                        return (labels, regions);
                     }
 
-                    regions.push(
-                        std::iter::repeat(())
-                            .fold_while(
-                                (VecDeque::from([pos]), HashSet::new()),
-                                |(mut queue, mut visited), _| match queue.pop_front() {
-                                    Some(pos) => {
-                                        if visited.contains(&pos) {
-                                            return FoldWhile::Continue((queue, visited));
-                                        }
-
-                                        visited.insert(pos);
-
-                                        queue.extend(
-                                            [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
-                                                .iter()
-                                                .map(move |offset| pos + offset)
-                                                .filter(|&pos| self.at(pos) == Some(&plant)),
-                                        );
-
-                                        FoldWhile::Continue((queue, visited))
-                                    }
-                                    _ => FoldWhile::Done((queue, visited)),
-                                },
+                    let label = regions.len();
+
+                    regions.push(Self::fill(map, &mut labels, size, label, pos));
+
+                    (labels, regions)
+                },
+            );
+
+        let mut garden = Self {
+            size,
+            labels: labels.into_iter().map(Option::unwrap).collect(),
+            regions,
+        };
+
+        let (neighbours, is_edge) = garden.adjacency();
+
+        (0..garden.regions.len()).for_each(|label| {
+            let holes = garden.holes(label, &neighbours, &is_edge);
+            let region = &mut garden.regions[label];
+
+            region.holes = holes.len() as i32;
+            region.enclosed = holes.into_iter().flatten().sorted().collect();
+        });
+
+        garden
+    }
+
+    /// Labels the region containing `pos` while measuring it.
+    fn fill(
+        map: &[&[u8]],
+        labels: &mut [Option<usize>],
+        size: IVec2,
+        label: usize,
+        pos: IVec2,
+    ) -> Region {
+        let index = |pos: IVec2| (pos.y * size.x + pos.x) as usize;
+        let plant = *map.at(pos).unwrap();
+        labels[index(pos)] = Some(label);
+
+        std::iter::repeat(())
+            .fold_while(
+                (VecDeque::from([pos]), Region::new(label, plant, pos)),
+                |(mut queue, mut region), _| {
+                    let curr = match queue.pop_front() {
+                        Some(curr) => curr,
+                        None => return FoldWhile::Done((queue, region)),
+                    };
+                    let is_same = |offset: IVec2| map.at(curr + offset) == Some(&plant);
+
+                    region.area += 1;
+                    region.bbox = (region.bbox.0.min(curr), region.bbox.1.max(curr));
+                    region.perimeter += DIRS.iter().filter(|&&dir| !is_same(dir)).count() as i32;
+                    region.sides += CORNERS
+                        .iter()
+                        .filter(|&&corner| {
+                            matches!(
+                                (
+                                    is_same(corner * IVec2::X),
+                                    is_same(corner * IVec2::Y),
+                                    is_same(corner),
+                                ),
+                                (false, false, _) | (true, true, false)
                             )
-                            .into_inner()
-                            .1,
-                    );
+                        })
+                        .count() as i32;
 
-                    visited.extend(regions.last().unwrap().iter().copied());
+                    DIRS.iter()
+                        .map(|&dir| curr + dir)
+                        .filter(|&next| map.at(next) == Some(&plant))
+                        .for_each(|next| {
+                            if labels[index(next)].is_none() {
+                                labels[index(next)] = Some(label);
+                                queue.push_back(next);
+                            }
+                        });
 
-                    (regions, visited)
+                    FoldWhile::Continue((queue, region))
                 },
             )
-            .0
+            .into_inner()
+            .1
+    }
+
+    pub fn label_at(&self, pos: IVec2) -> Option<usize> {
+        match pos.cmpge(IVec2::ZERO).all() && pos.cmplt(self.size).all() {
+            true => Some(self.labels[(pos.y * self.size.x + pos.x) as usize]),
+            false => None,
+        }
+    }
+
+    /// Returns the labels of the regions adjacent to each region, and whether each region
+    /// touches the edge of the map.
+    fn adjacency(&self) -> (Vec<HashSet<usize>>, Vec<bool>) {
+        (0..self.size.y)
+            .cartesian_product(0..self.size.x)
+            .map(|(y, x)| ivec2(x, y))
+            .fold(
+                (
+                    vec![HashSet::new(); self.regions.len()],
+                    vec![false; self.regions.len()],
+                ),
+                |(mut neighbours, mut is_edge), pos| {
+                    let label = self.label_at(pos).unwrap();
+
+                    DIRS.iter()
+                        .map(|&dir| self.label_at(pos + dir))
+                        .for_each(|next| match next {
+                            Some(next) if next != label => {
+                                neighbours[label].insert(next);
+                            }
+                            Some(_) => {}
+                            None => is_edge[label] = true,
+                        });
+
+                    (neighbours, is_edge)
+                },
+            )
+    }
+
+    /// Returns the regions inside each hole of the region `label`, as the groups of other
+    /// connected regions that cannot reach the edge of the map.
+    fn holes(
+        &self,
+        label: usize,
+        neighbours: &[HashSet<usize>],
+        is_edge: &[bool],
+    ) -> Vec<Vec<usize>> {
+        (0..self.regions.len())
+            .filter(|&other| other != label)
+            .fold(
+                (HashSet::from([label]), Vec::new()),
+                |(mut visited, mut holes), other| {
+                    if visited.contains(&other) {
+                        return (visited, holes);
+                    }
+
+                    let group = std::iter::repeat(())
+                        .fold_while((vec![other], Vec::new()), |(mut stack, mut group), _| {
+                            let curr = match stack.pop() {
+                                Some(curr) => curr,
+                                None => return FoldWhile::Done((stack, group)),
+                            };
+
+                            if !visited.insert(curr) {
+                                return FoldWhile::Continue((stack, group));
+                            }
+
+                            group.push(curr);
+                            stack.extend(neighbours[curr].iter().copied());
+
+                            FoldWhile::Continue((stack, group))
+                        })
+                        .into_inner()
+                        .1;
+
+                    if group.iter().all(|&region| !is_edge[region]) {
+                        holes.push(group);
+                    }
+
+                    (visited, holes)
+                },
+            )
+            .1
     }
 }
 
-fn parse_input(input: &str) -> Vec<&[u8]> {
+pub fn parse_input(input: &str) -> Vec<&[u8]> {
     input.lines().map(|line| line.trim().as_bytes()).collect()
 }
 
 fn solution_part_1(map: &[&[u8]]) -> i32 {
-    map.regions()
-        .into_iter()
-        .map(|region| {
-            let area = region.len() as i32;
-            let perimeter = region
-                .iter()
-                .map(|&pos| {
-                    [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
-                        .iter()
-                        .map(move |offset| pos + offset)
-                        .filter(|&pos| !region.contains(&pos))
-                        .count()
-                })
-                .sum::<usize>() as i32;
-
-            area * perimeter
-        })
-        .sum()
+    Garden::new(map).regions.iter().map(Region::price).sum()
 }
 
 fn solution_part_2(map: &[&[u8]]) -> i32 {
-    map.regions()
-        .into_iter()
-        .map(|region| {
-            let area = region.len() as i32;
-            let side_count = [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
-                .into_iter()
-                .map(|dir| {
-                    let sides = region
-                        .iter()
-                        .filter(|&pos| !region.contains(&(pos + dir)))
-                        .collect::<HashSet<_>>();
-
-                    let cross_axis = match dir {
-                        IVec2::X | IVec2::NEG_X => IVec2::Y,
-                        IVec2::Y | IVec2::NEG_Y => IVec2::X,
-                        _ => unreachable!(),
-                    };
-
-                    sides
-                        .iter()
-                        .map(|&pos| pos + cross_axis)
-                        .filter(|&pos| !sides.contains(&pos))
-                        .count() as i32
-                })
-                .sum::<i32>();
-
-            area * side_count
-        })
+    Garden::new(map)
+        .regions
+        .iter()
+        .map(Region::discounted_price)
         .sum()
 }
 
@@ -147,6 +281,24 @@ fn main() {
 
     let map = parse_input(&input);
 
+    if let Some("prices") = std::env::args().nth(1).as_deref() {
+        Garden::new(&map).regions.iter().for_each(|region| {
+            println!(
+                "#{:<4} {} area {:>4} perimeter {:>4} sides {:>4} price {:>6} discounted {:>6} holes {} enclosing {:?}",
+                region.label,
+                region.plant as char,
+                region.area,
+                region.perimeter,
+                region.sides,
+                region.price(),
+                region.discounted_price(),
+                region.holes,
+                region.enclosed,
+            )
+        });
+        return;
+    }
+
     println!("Part 1 solution: {}", solution_part_1(&map));
     println!("Part 2 solution: {}", solution_part_2(&map));
 }
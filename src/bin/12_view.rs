@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use aoc_2024::input;
+use glam::*;
+use itertools::Itertools;
+
+#[path = "12.rs"]
+#[allow(dead_code)]
+mod day;
+
+use day::Garden;
+
+/// Returns a colour for the region, spreading consecutive labels around the hue wheel.
+fn rgb(label: usize) -> [u8; 3] {
+    let hue = (label as f32 * 0.618_034).fract() * 6.0;
+    let (saturation, value) = (0.6, 0.9);
+
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as i32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let min = value - chroma;
+
+    [r, g, b].map(|c| ((c + min) * 255.0) as u8)
+}
+
+/// 3x5 glyphs of the digits, one row of 3 bits per line from the top.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Returns whether the pixel at `pos` within the glyph of `digit` is set.
+fn is_glyph(digit: u8, pos: IVec2) -> bool {
+    (0..3).contains(&pos.x)
+        && (0..5).contains(&pos.y)
+        && DIGITS[(digit - b'0') as usize][pos.y as usize] >> (2 - pos.x) & 1 == 1
+}
+
+/// Returns the text written over each cell to show the region labels, `width` characters per cell.
+///
+/// Each label is written from the start of the longest row of cells in its region, unless it does
+/// not fit, in which case the region is only matched to the legend by its colour.
+fn label_cells(garden: &Garden, width: usize) -> HashMap<IVec2, String> {
+    let runs = (0..garden.size.y).fold(HashMap::new(), |runs, y| {
+        (0..garden.size.x)
+            .map(|x| garden.label_at(ivec2(x, y)).unwrap())
+            .dedup_with_count()
+            .fold((runs, 0), |(mut runs, x), (len, label)| {
+                let run = runs.entry(label).or_insert((ivec2(x, y), len));
+                if len > run.1 {
+                    *run = (ivec2(x, y), len);
+                }
+
+                (runs, x + len as i32)
+            })
+            .0
+    });
+
+    garden
+        .regions
+        .iter()
+        .map(|region| (runs[&region.label], region.label.to_string()))
+        .filter(|&((_, len), ref text)| text.len() <= len * width)
+        .flat_map(|((start, _), text)| {
+            text.into_bytes()
+                .chunks(width)
+                .enumerate()
+                .map(|(i, chunk)| {
+                    (
+                        start + IVec2::X * i as i32,
+                        format!("{:<width$}", String::from_utf8_lossy(chunk)),
+                    )
+                })
+                .collect_vec()
+        })
+        .collect()
+}
+
+/// Renders each cell as its plant followed by a space, or as part of its region's label.
+fn ansi(map: &[&[u8]], garden: &Garden) -> String {
+    let labels = label_cells(garden, 2);
+
+    (0..garden.size.y)
+        .map(|y| {
+            (0..garden.size.x)
+                .map(|x| {
+                    let [r, g, b] = rgb(garden.label_at(ivec2(x, y)).unwrap());
+                    let text = labels
+                        .get(&ivec2(x, y))
+                        .cloned()
+                        .unwrap_or_else(|| format!("{} ", map[y as usize][x as usize] as char));
+                    format!("\x1b[30;48;2;{r};{g};{b}m{text}")
+                })
+                .join("")
+                + "\x1b[0m"
+        })
+        .join("\n")
+}
+
+fn legend(garden: &Garden) -> String {
+    garden
+        .regions
+        .iter()
+        .map(|region| {
+            let [r, g, b] = rgb(region.label);
+            format!(
+                "\x1b[48;2;{r};{g};{b}m  \x1b[0m #{} {}: {}/{}/{} (area/perimeter/sides)",
+                region.label, region.plant as char, region.area, region.perimeter, region.sides,
+            )
+        })
+        .join("\n")
+}
+
+/// Renders each cell as a `scale` square, darkening the edges between regions and writing the
+/// region labels in black one digit per cell.
+fn ppm(garden: &Garden, scale: i32) -> Vec<u8> {
+    let size = garden.size * scale;
+    let labels = label_cells(garden, 1);
+
+    format!("P6\n{} {}\n255\n", size.x, size.y)
+        .into_bytes()
+        .into_iter()
+        .chain((0..size.y).cartesian_product(0..size.x).flat_map(|(y, x)| {
+            let (cell, local) = (ivec2(x, y) / scale, ivec2(x, y) % scale);
+            let label = garden.label_at(cell);
+
+            let is_edge = [
+                (local.x == 0, IVec2::NEG_X),
+                (local.y == 0, IVec2::NEG_Y),
+                (local.x == scale - 1, IVec2::X),
+                (local.y == scale - 1, IVec2::Y),
+            ]
+            .iter()
+            .any(|&(is_side, dir)| is_side && garden.label_at(cell + dir) != label);
+
+            // Glyphs are drawn on an 8x8 grid per cell, leaving a margin inside the edges
+            let is_text = labels
+                .get(&cell)
+                .is_some_and(|text| is_glyph(text.as_bytes()[0], local * 8 / scale - ivec2(2, 1)));
+
+            rgb(label.unwrap()).map(|c| match (is_text, is_edge) {
+                (true, _) => 0,
+                (false, true) => c / 3,
+                (false, false) => c,
+            })
+        }))
+        .collect()
+}
+
+fn main() {
+    let input = input(12);
+    let map = day::parse_input(&input);
+    let garden = Garden::new(&map);
+
+    match std::env::args().nth(1).as_deref() {
+        Some("ppm") => {
+            let path = std::env::args().nth(2).unwrap_or("12.ppm".to_string());
+            std::fs::write(&path, ppm(&garden, 8)).expect("image should be written");
+            println!("Written to {path}");
+        }
+        _ => println!("{}", ansi(&map, &garden)),
+    }
+
+    println!("{}", legend(&garden));
+}
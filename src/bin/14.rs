@@ -76,38 +76,199 @@ pub fn solution_part_1(robots: &[Robot], size: IVec2) -> i32 {
         .product()
 }
 
-pub fn solution_part_2(robots: &[Robot], size: IVec2) -> i32 {
-    let (i, easter_egg) = (0..i32::MAX)
-        .map(|i| {
-            robots
-                .iter()
-                .map(|robot| (robot.p + robot.v * i).rem_euclid(size))
-                .fold(
-                    (0..size.y)
-                        .map(|_| (0..size.x).map(|_| b' ').collect_vec())
-                        .collect_vec(),
-                    |mut map, pos| {
-                        map[pos.y as usize][pos.x as usize] = b'X';
-                        map
-                    },
-                )
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detector {
+    /// Lowest variance of the x and y coordinates, combined by CRT.
+    Variance,
+    /// Lowest Shannon entropy of the x and y histograms, combined by CRT.
+    Entropy,
+    /// Largest 4-connected cluster of robots, scanned over one full cycle.
+    LargestComponent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    pub time: i32,
+    /// Lower is more likely to be the easter egg.
+    pub score: f64,
+}
+
+impl Detector {
+    /// Returns up to `count` candidate frames within one full cycle, best first.
+    pub fn candidates(&self, robots: &[Robot], size: IVec2, count: usize) -> Vec<Candidate> {
+        match self {
+            Detector::Variance => Self::axis_candidates(robots, size, count, variance),
+            Detector::Entropy => Self::axis_candidates(robots, size, count, entropy),
+            Detector::LargestComponent => (0..period(size))
+                .map(|time| Candidate {
+                    time,
+                    score: -(largest_component(&positions_at(robots, size, time), size) as f64),
+                })
+                .sorted_by(|a, b| a.score.total_cmp(&b.score))
+                .take(count)
+                .collect(),
+        }
+    }
+
+    fn axis_candidates(
+        robots: &[Robot],
+        size: IVec2,
+        count: usize,
+        score: impl Fn(&[i32], i32) -> f64,
+    ) -> Vec<Candidate> {
+        let axis_scores = |axis: usize| {
+            (0..size[axis])
+                .map(|time| {
+                    let values = positions_at(robots, size, time)
+                        .into_iter()
+                        .map(|pos| pos[axis])
+                        .collect_vec();
+
+                    (time, score(&values, size[axis]))
+                })
+                .sorted_by(|(_, a), (_, b)| a.total_cmp(b))
+                .take(count)
+                .collect_vec()
+        };
+
+        axis_scores(0)
+            .into_iter()
+            .cartesian_product(axis_scores(1))
+            .filter_map(|((time_x, score_x), (time_y, score_y))| {
+                Some(Candidate {
+                    time: crt(time_x, size.x, time_y, size.y)?,
+                    score: score_x + score_y,
+                })
+            })
+            .sorted_by(|a, b| a.score.total_cmp(&b.score))
+            .dedup_by(|a, b| a.time == b.time)
+            .take(count)
+            .collect()
+    }
+}
+
+pub fn positions_at(robots: &[Robot], size: IVec2, time: i32) -> Vec<IVec2> {
+    robots
+        .iter()
+        .map(|robot| (robot.p + robot.v * time).rem_euclid(size))
+        .collect()
+}
+
+/// Positions repeat after this many seconds.
+pub fn period(size: IVec2) -> i32 {
+    size.x / gcd(size.x, size.y) * size.y
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    match b {
+        0 => a.abs(),
+        _ => gcd(b, a.rem_euclid(b)),
+    }
+}
+
+/// Returns the smallest non-negative `t` with `t = a (mod m)` and `t = b (mod n)`.
+fn crt(a: i32, m: i32, b: i32, n: i32) -> Option<i32> {
+    (0..n / gcd(m, n))
+        .map(|k| a + m * k)
+        .find(|t| (t - b).rem_euclid(n) == 0)
+}
+
+fn variance(values: &[i32], _: i32) -> f64 {
+    let n = values.len() as f64;
+    let mean = values.iter().map(|&v| v as f64).sum::<f64>() / n;
+
+    values
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n
+}
+
+fn entropy(values: &[i32], len: i32) -> f64 {
+    let n = values.len() as f64;
+
+    values
+        .iter()
+        .fold(vec![0usize; len as usize], |mut counts, &v| {
+            counts[v as usize] += 1;
+            counts
         })
-        .enumerate()
-        .find(|(_, map)| {
-            map.iter()
-                .any(|row| row.windows(10).any(|c| c == [b'X'; 10]))
+        .into_iter()
+        .filter(|&count| count > 0)
+        .map(|count| count as f64 / n)
+        .map(|p| -p * p.log2())
+        .sum()
+}
+
+fn largest_component(positions: &[IVec2], size: IVec2) -> usize {
+    let index = |pos: IVec2| (pos.y * size.x + pos.x) as usize;
+
+    let mut occupied = positions.iter().fold(
+        vec![false; (size.x * size.y) as usize],
+        |mut occupied, &pos| {
+            occupied[index(pos)] = true;
+            occupied
+        },
+    );
+
+    positions
+        .iter()
+        .map(|&start| {
+            let mut stack = vec![start];
+            let mut count = 0;
+
+            while let Some(pos) = stack.pop() {
+                if !occupied[index(pos)] {
+                    continue;
+                }
+
+                occupied[index(pos)] = false;
+                count += 1;
+
+                stack.extend(
+                    [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
+                        .into_iter()
+                        .map(|offset| pos + offset)
+                        .filter(|pos| pos.cmpge(IVec2::ZERO).all() && pos.cmplt(size).all()),
+                );
+            }
+
+            count
         })
+        .max()
+        .unwrap_or(0)
+}
+
+pub fn render(positions: &[IVec2], size: IVec2) -> String {
+    positions
+        .iter()
+        .fold(
+            (0..size.y)
+                .map(|_| (0..size.x).map(|_| b' ').collect_vec())
+                .collect_vec(),
+            |mut map, pos| {
+                map[pos.y as usize][pos.x as usize] = b'X';
+                map
+            },
+        )
+        .iter()
+        .flat_map(|row| std::str::from_utf8(row).map(str::to_owned))
+        .join("\n")
+}
+
+pub fn solution_part_2(robots: &[Robot], size: IVec2) -> i32 {
+    let easter_egg = Detector::Variance
+        .candidates(robots, size, 1)
+        .into_iter()
+        .next()
         .unwrap();
 
     println!(
         "{}",
-        easter_egg
-            .iter()
-            .flat_map(|row| std::str::from_utf8(row))
-            .join("\n")
+        render(&positions_at(robots, size, easter_egg.time), size)
     );
 
-    i as i32
+    easter_egg.time
 }
 
 fn main() {
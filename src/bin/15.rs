@@ -1,4 +1,4 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::VecDeque;
 
 use aoc_2024::input;
 use glam::*;
@@ -56,142 +56,260 @@ pub fn parse_input(input: &str) -> (Vec<Vec<u8>>, IVec2, Vec<IVec2>) {
     (map, pos, deltas)
 }
 
-pub fn solution_part_1(map: &[Vec<u8>], pos: IVec2, deltas: &[IVec2]) -> i32 {
-    let mut map = map.iter().map(|row| row.to_vec()).collect_vec();
-    map.set_at(pos, b'.');
-
-    deltas
-        .iter()
-        .fold((map, pos), |(mut map, pos), delta| {
-            let (end, end_c) = (1..)
-                .map(|step| pos + delta * step)
-                .find(|&offset| matches!(map.at(offset), b'#' | b'.'))
-                .map(|end| (end, map.at(end)))
-                .unwrap();
-
-            if end_c == b'#' {
-                return (map, pos);
-            }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepOutcome {
+    Moved,
+    /// Left edges of the pushed boxes after the push.
+    Pushed(Vec<IVec2>),
+    Blocked,
+}
 
-            (1..)
-                .map(|step| pos + delta * step)
-                .take_while(|&offset| offset - delta != end)
-                .for_each(|offset| {
-                    map.set_at(offset, if offset == pos + delta { b'.' } else { b'O' })
-                });
+impl StepOutcome {
+    pub fn pushed(&self) -> &[IVec2] {
+        match self {
+            StepOutcome::Pushed(boxes) => boxes,
+            _ => &[],
+        }
+    }
+}
 
-            (map, pos + delta)
-        })
-        .0
-        .into_iter()
-        .enumerate()
-        .flat_map(|(y, row)| {
-            row.into_iter()
-                .enumerate()
-                .map(move |(x, c)| (ivec2(x as i32, y as i32), c))
-        })
-        .filter(|&(_, c)| c == b'O')
-        .map(|(pos, _)| pos.y * 100 + pos.x)
-        .sum()
+#[derive(Debug, Clone)]
+pub struct Warehouse {
+    pub map: Vec<Vec<u8>>,
+    pub pos: IVec2,
+    /// Number of cells each box spans horizontally, the map is widened by the same factor.
+    pub box_width: i32,
 }
 
-pub fn solution_part_2(map: &[Vec<u8>], pos: IVec2, deltas: &[IVec2]) -> i32 {
-    let mut map = map
-        .iter()
-        .map(|row| {
-            row.iter()
-                .flat_map(|&c| if c == b'O' { [b'[', b']'] } else { [c, c] })
-                .collect_vec()
-        })
-        .collect_vec();
+impl Warehouse {
+    pub fn new(map: &[Vec<u8>], pos: IVec2, box_width: i32) -> Self {
+        assert!(box_width >= 1, "box width should be at least 1");
+
+        let box_cells = match box_width {
+            1 => vec![b'O'],
+            _ => std::iter::once(b'[')
+                .chain(std::iter::repeat_n(b'=', box_width as usize - 2))
+                .chain(std::iter::once(b']'))
+                .collect_vec(),
+        };
+
+        let mut map = map
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .flat_map(|&c| match c {
+                        b'O' => box_cells.clone(),
+                        _ => vec![c; box_width as usize],
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
 
-    let pos = pos * ivec2(2, 1);
+        let pos = pos * ivec2(box_width, 1);
 
-    map.set_at(pos, b'.');
+        map.set_at(pos, b'.');
 
-    deltas
-        .iter()
-        .fold((map, pos), |(mut map, pos), delta| {
-            if delta.x != 0 {
-                let (end, end_c) = (1..)
-                    .map(|step| pos + delta * step)
-                    .find(|&offset| matches!(map.at(offset), b'#' | b'.'))
-                    .map(|end| (end, map.at(end)))
-                    .unwrap();
-
-                if end_c == b'#' {
-                    return (map, pos);
-                }
+        Self {
+            map,
+            pos,
+            box_width,
+        }
+    }
 
-                (1..)
-                    .map(|step| pos + delta * step)
-                    .take_while(|&offset| offset - delta != end)
-                    .collect_vec()
-                    .into_iter()
-                    .rev()
-                    .for_each(|offset| map.set_at(offset, map.at(offset - delta)));
-            } else {
-                let (_, boxes, blocked) = std::iter::repeat(())
-                    .fold_while(
-                        (VecDeque::from([pos]), HashSet::new(), false),
-                        |(mut queue, mut visited, blocked), _| {
-                            let curr = match queue.pop_front() {
-                                Some(curr) => curr,
-                                None => return FoldWhile::Done((queue, visited, blocked)),
-                            };
-
-                            if visited.contains(&curr) {
-                                return FoldWhile::Continue((queue, visited, blocked));
-                            }
-
-                            match map.at(curr) {
-                                b'[' => {
-                                    queue.push_back(curr + IVec2::X);
-                                    visited.insert(curr);
-                                }
-                                b']' => queue.push_back(curr - IVec2::X),
-                                _ => {}
-                            }
-
-                            match map.at(curr + delta) {
-                                b'[' | b']' => queue.push_back(curr + delta),
-                                b'#' => return FoldWhile::Done((queue, visited, true)),
-                                _ => {}
-                            }
-
-                            FoldWhile::Continue((queue, visited, blocked))
-                        },
-                    )
-                    .into_inner();
-
-                if blocked {
-                    return (map, pos);
-                }
+    pub fn step(&mut self, delta: IVec2) -> StepOutcome {
+        let pushed = match delta.x {
+            0 => self.push_area(delta),
+            _ => self.push_line(delta),
+        };
 
-                boxes.iter().for_each(|&b| {
-                    map.set_at(b, b'.');
-                    map.set_at(b + IVec2::X, b'.');
-                });
+        match pushed {
+            None => StepOutcome::Blocked,
+            Some(pushed) => {
+                self.pos += delta;
 
-                boxes.into_iter().map(|b| b + delta).for_each(|b| {
-                    map.set_at(b, b'[');
-                    map.set_at(b + IVec2::X, b']');
-                });
+                match pushed.is_empty() {
+                    true => StepOutcome::Moved,
+                    false => StepOutcome::Pushed(pushed),
+                }
             }
+        }
+    }
+
+    /// Reverts a step by `delta` that had the given outcome, moving the robot and any pushed boxes
+    /// back.
+    pub fn unstep(&mut self, delta: IVec2, outcome: &StepOutcome) {
+        if *outcome == StepOutcome::Blocked {
+            return;
+        }
 
-            (map, pos + delta)
+        self.shift_boxes(outcome.pushed(), -delta);
+        self.pos -= delta;
+    }
+
+    /// Returns the left edge of the box covering `pos`, if any.
+    pub fn box_at(&self, pos: IVec2) -> Option<IVec2> {
+        match self.map.at(pos) {
+            b'O' | b'[' => Some(pos),
+            b'=' | b']' => (1..)
+                .map(|step| pos - IVec2::X * step)
+                .find(|&offset| self.map.at(offset) == b'['),
+            _ => None,
+        }
+    }
+
+    /// Returns every intermediate state along with the outcome of the step leading to it.
+    pub fn states<'a>(
+        &self,
+        deltas: &'a [IVec2],
+    ) -> impl Iterator<Item = (Warehouse, StepOutcome)> + 'a {
+        deltas.iter().scan(self.clone(), |warehouse, &delta| {
+            let outcome = warehouse.step(delta);
+            Some((warehouse.clone(), outcome))
         })
-        .0
-        .into_iter()
-        .enumerate()
-        .flat_map(|(y, row)| {
-            row.into_iter()
+    }
+
+    pub fn boxes(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.map
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(x, &c)| (ivec2(x as i32, y as i32), c))
+            })
+            .filter(|&(_, c)| matches!(c, b'O' | b'['))
+            .map(|(pos, _)| pos)
+    }
+
+    pub fn gps_sum(&self) -> i32 {
+        self.boxes().map(|pos| pos.y * 100 + pos.x).sum()
+    }
+
+    pub fn render(&self) -> String {
+        self.map
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(x, &c)| match ivec2(x as i32, y as i32) == self.pos {
+                        true => '@',
+                        false => c as char,
+                    })
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
+
+    /// Pushes the straight line of box cells in front of the robot horizontally.
+    fn push_line(&mut self, delta: IVec2) -> Option<Vec<IVec2>> {
+        let end = (1..)
+            .map(|step| self.pos + delta * step)
+            .find(|&offset| matches!(self.map.at(offset), b'#' | b'.'))
+            .unwrap();
+
+        if self.map.at(end) == b'#' {
+            return None;
+        }
+
+        let cells = (1..)
+            .map(|step| self.pos + delta * step)
+            .take_while(|&offset| offset - delta != end)
+            .collect_vec();
+
+        cells
+            .iter()
+            .rev()
+            .for_each(|&offset| self.map.set_at(offset, self.map.at(offset - delta)));
+
+        Some(
+            cells
+                .into_iter()
+                .skip(1)
+                .filter(|&offset| matches!(self.map.at(offset), b'O' | b'['))
+                .collect(),
+        )
+    }
+
+    /// Pushes every box connected to the robot in the vertical direction.
+    fn push_area(&mut self, delta: IVec2) -> Option<Vec<IVec2>> {
+        let (_, boxes, blocked) = std::iter::repeat(())
+            .fold_while(
+                (VecDeque::from([self.pos + delta]), Vec::new(), false),
+                |(mut queue, mut boxes, blocked), _| {
+                    let curr = match queue.pop_front() {
+                        Some(curr) => curr,
+                        None => return FoldWhile::Done((queue, boxes, blocked)),
+                    };
+
+                    let left = match (self.map.at(curr), self.box_at(curr)) {
+                        (b'#', _) => return FoldWhile::Done((queue, boxes, true)),
+                        (_, Some(left)) if !boxes.contains(&left) => left,
+                        _ => return FoldWhile::Continue((queue, boxes, blocked)),
+                    };
+
+                    boxes.push(left);
+                    queue.extend((0..self.box_width).map(|i| left + ivec2(i, 0) + delta));
+
+                    FoldWhile::Continue((queue, boxes, blocked))
+                },
+            )
+            .into_inner();
+
+        if blocked {
+            return None;
+        }
+
+        self.shift_boxes(&boxes, delta);
+
+        Some(boxes.into_iter().map(|b| b + delta).collect())
+    }
+
+    /// Moves the boxes with the given left edges by `delta`, into cells that are free or vacated.
+    fn shift_boxes(&mut self, boxes: &[IVec2], delta: IVec2) {
+        let cells = boxes
+            .iter()
+            .map(|&b| {
+                (
+                    b,
+                    (0..self.box_width)
+                        .map(|i| self.map.at(b + ivec2(i, 0)))
+                        .collect_vec(),
+                )
+            })
+            .collect_vec();
+
+        cells.iter().for_each(|(b, row)| {
+            (0..row.len() as i32).for_each(|i| self.map.set_at(b + ivec2(i, 0), b'.'))
+        });
+
+        cells.iter().for_each(|(b, row)| {
+            row.iter()
                 .enumerate()
-                .map(move |(x, c)| (ivec2(x as i32, y as i32), c))
-        })
-        .filter(|&(_, c)| c == b'[')
-        .map(|(pos, _)| pos.y * 100 + pos.x)
-        .sum()
+                .for_each(|(i, &c)| self.map.set_at(b + delta + ivec2(i as i32, 0), c))
+        });
+    }
+}
+
+pub fn solution(map: &[Vec<u8>], pos: IVec2, deltas: &[IVec2], box_width: i32) -> i32 {
+    deltas
+        .iter()
+        .fold(
+            Warehouse::new(map, pos, box_width),
+            |mut warehouse, &delta| {
+                warehouse.step(delta);
+                warehouse
+            },
+        )
+        .gps_sum()
+}
+
+pub fn solution_part_1(map: &[Vec<u8>], pos: IVec2, deltas: &[IVec2]) -> i32 {
+    solution(map, pos, deltas, 1)
+}
+
+pub fn solution_part_2(map: &[Vec<u8>], pos: IVec2, deltas: &[IVec2]) -> i32 {
+    solution(map, pos, deltas, 2)
 }
 
 fn main() {
@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+
+use aoc_2024::input;
+use crossterm::{
+    event::{self, Event, KeyCode, MouseEventKind},
+    terminal::{disable_raw_mode, enable_raw_mode},
+    ExecutableCommand,
+};
+use glam::*;
+use itertools::Itertools;
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+};
+
+#[path = "15.rs"]
+#[allow(dead_code)]
+mod day;
+
+use day::{StepOutcome, Warehouse};
+
+struct Frame {
+    warehouse: Warehouse,
+    outcome: StepOutcome,
+    next: usize,
+}
+
+/// What is needed to revert a step, without keeping a copy of the map.
+struct Undo {
+    delta: IVec2,
+    outcome: StepOutcome,
+    prev_outcome: StepOutcome,
+    is_replay: bool,
+}
+
+struct State {
+    frame: Frame,
+    history: Vec<Undo>,
+}
+
+impl State {
+    fn new(map: &[Vec<u8>], pos: IVec2, box_width: i32) -> Self {
+        Self {
+            frame: Frame {
+                warehouse: Warehouse::new(map, pos, box_width),
+                outcome: StepOutcome::Moved,
+                next: 0,
+            },
+            history: Vec::new(),
+        }
+    }
+
+    fn step(&mut self, delta: IVec2, is_replay: bool) {
+        let outcome = self.frame.warehouse.step(delta);
+
+        self.history.push(Undo {
+            delta,
+            outcome: outcome.clone(),
+            prev_outcome: std::mem::replace(&mut self.frame.outcome, outcome),
+            is_replay,
+        });
+
+        if is_replay {
+            self.frame.next += 1;
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(undo) = self.history.pop() {
+            self.frame.warehouse.unstep(undo.delta, &undo.outcome);
+            self.frame.outcome = undo.prev_outcome;
+
+            if undo.is_replay {
+                self.frame.next -= 1;
+            }
+        }
+    }
+
+    fn box_width(&self) -> i32 {
+        self.frame.warehouse.box_width
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let warehouse = &self.frame.warehouse;
+        let pushed = self
+            .frame
+            .outcome
+            .pushed()
+            .iter()
+            .flat_map(|&b| (0..warehouse.box_width).map(move |i| b + ivec2(i, 0)))
+            .collect::<HashSet<_>>();
+
+        warehouse
+            .map
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                Line::from(
+                    row.iter()
+                        .enumerate()
+                        .map(|(x, &c)| {
+                            let pos = ivec2(x as i32, y as i32);
+
+                            match c {
+                                _ if pos == warehouse.pos => Span::styled(
+                                    "@",
+                                    Style::new().fg(Color::Green).add_modifier(Modifier::BOLD),
+                                ),
+                                b'#' => Span::styled("#", Style::new().fg(Color::DarkGray)),
+                                b'O' | b'[' | b'=' | b']' => Span::styled(
+                                    (c as char).to_string(),
+                                    match pushed.contains(&pos) {
+                                        true => {
+                                            Style::new().fg(Color::Red).add_modifier(Modifier::BOLD)
+                                        }
+                                        false => Style::new().fg(Color::Yellow),
+                                    },
+                                ),
+                                _ => Span::raw(" "),
+                            }
+                        })
+                        .collect_vec(),
+                )
+            })
+            .collect()
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    log::info!("Parsing day 15 input");
+
+    let input = input(15);
+    let (map, pos, deltas) = day::parse_input(&input);
+
+    log::info!("Starting terminal");
+
+    std::io::stdout()
+        .execute(crossterm::event::EnableMouseCapture)
+        .expect("mouse should be captured");
+    enable_raw_mode().expect("raw mode should be enabled");
+
+    let mut terminal = ratatui::init();
+    terminal.clear().expect("terminal should be cleared");
+
+    let mut state = State::new(&map, pos, 1);
+    let mut speed: i32 = 10; // moves per second
+    let mut is_playing = false;
+    let mut last_step = std::time::Instant::now();
+    let mut is_running = true;
+
+    while is_running {
+        if is_playing {
+            let interval = std::time::Duration::from_secs_f32(1.0 / speed.max(1) as f32);
+
+            while last_step.elapsed() >= interval && state.frame.next < deltas.len() {
+                state.step(deltas[state.frame.next], true);
+                last_step += interval;
+            }
+
+            if state.frame.next >= deltas.len() {
+                is_playing = false;
+            }
+        }
+
+        let term_size = (
+            state.frame.warehouse.map.first().map_or(0, Vec::len) as u16 + 2,
+            state.frame.warehouse.map.len() as u16 + 2,
+        );
+
+        terminal
+            .draw(|frame| {
+                let [center] = Layout::horizontal([Constraint::Length(term_size.0)])
+                    .flex(Flex::Center)
+                    .areas(frame.area());
+                let [center] = Layout::vertical([Constraint::Length(term_size.1)])
+                    .flex(Flex::Center)
+                    .areas(center);
+
+                let paragraph = Paragraph::new(state.lines()).block(
+                    Block::bordered()
+                        .title_top(format!(
+                            "Advent of Code 2024 - Day 15 Warehouse (Box Width {})",
+                            state.box_width(),
+                        ))
+                        .title_top(
+                            Line::from("Space play/pause | Arrows move | U undo | R reset | W width | Q quit")
+                                .right_aligned(),
+                        )
+                        .title_bottom(format!(
+                            "Move: {}/{} | GPS: {} | Speed: {}/s | {}",
+                            state.frame.next,
+                            deltas.len(),
+                            state.frame.warehouse.gps_sum(),
+                            speed,
+                            if is_playing { "Playing" } else { "Paused" },
+                        )),
+                );
+
+                frame.render_widget(paragraph, center);
+            })
+            .expect("frame should be rendered");
+
+        while event::poll(std::time::Duration::from_millis(10)).expect("event should be polled") {
+            match event::read().expect("event should be read") {
+                Event::Key(event) => match event.code {
+                    KeyCode::Char(' ') => {
+                        is_playing = !is_playing;
+                        last_step = std::time::Instant::now();
+                    }
+                    KeyCode::Up | KeyCode::Right | KeyCode::Down | KeyCode::Left => {
+                        is_playing = false;
+                        state.step(
+                            match event.code {
+                                KeyCode::Up => ivec2(0, -1),
+                                KeyCode::Right => ivec2(1, 0),
+                                KeyCode::Down => ivec2(0, 1),
+                                _ => ivec2(-1, 0),
+                            },
+                            false,
+                        );
+                    }
+                    KeyCode::Char('u') => {
+                        is_playing = false;
+                        state.undo();
+                    }
+                    KeyCode::Char('r') => {
+                        is_playing = false;
+                        state = State::new(&map, pos, state.box_width());
+                    }
+                    KeyCode::Char('w') => {
+                        is_playing = false;
+                        state = State::new(&map, pos, state.box_width() % 4 + 1);
+                        terminal.clear().expect("terminal should be cleared");
+                    }
+                    KeyCode::Char('q') => {
+                        is_running = false;
+                        break;
+                    }
+                    _ => {}
+                },
+                Event::Mouse(event) => match event.kind {
+                    MouseEventKind::ScrollUp => {
+                        let speed_delta = match speed {
+                            0..10 => 1,
+                            10..100 => 10,
+                            100..1000 => 100,
+                            _ => 0,
+                        };
+                        speed = (speed + speed_delta).min(1000);
+                    }
+                    MouseEventKind::ScrollDown => {
+                        let speed_delta = match speed {
+                            0..=10 => 1,
+                            11..=100 => 10,
+                            101..=1000 => 100,
+                            _ => 0,
+                        };
+                        speed = (speed - speed_delta).max(1);
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+
+    ratatui::restore();
+
+    disable_raw_mode().expect("raw mode should be disabled");
+    std::io::stdout()
+        .execute(crossterm::event::DisableMouseCapture)
+        .expect("mouse should be released");
+}
@@ -37,6 +37,10 @@ impl Dir {
         let value = self as u8;
         [value.wrapping_sub(1), value.wrapping_add(1)].map(Self::from_u8)
     }
+
+    pub fn reverse(self) -> Self {
+        Self::from_u8(self as u8 + 2)
+    }
 }
 
 impl From<Dir> for IVec2 {
@@ -50,6 +54,59 @@ impl From<Dir> for IVec2 {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MazeCosts {
+    /// Cost of moving one tile forward.
+    pub step: i32,
+    /// Cost of rotating 90 degrees.
+    pub turn: i32,
+    /// Whether the reindeer may rotate 180 degrees on the spot, costing two turns.
+    pub u_turn: bool,
+    pub start_dir: Dir,
+}
+
+impl Default for MazeCosts {
+    fn default() -> Self {
+        Self::new(1, 1000, false, Dir::X)
+    }
+}
+
+impl MazeCosts {
+    pub fn new(step: i32, turn: i32, u_turn: bool, start_dir: Dir) -> Self {
+        let costs = Self {
+            step,
+            turn,
+            u_turn,
+            start_dir,
+        };
+        costs.validate();
+        costs
+    }
+
+    /// Panics unless every move has a positive cost, which the search and path counting rely on.
+    pub fn validate(&self) {
+        assert!(
+            self.step >= 1,
+            "step cost should be at least 1, got {}",
+            self.step
+        );
+        assert!(
+            self.turn >= 0,
+            "turn cost should not be negative, got {}",
+            self.turn
+        );
+    }
+
+    /// Returns the cost and new facing of every move from facing `dir`, each ending one tile
+    /// forward in the new facing.
+    pub fn moves(&self, dir: Dir) -> impl Iterator<Item = (i32, Dir)> + '_ {
+        std::iter::once((0, dir))
+            .chain(dir.turns().map(|dir| (self.turn, dir)))
+            .chain(self.u_turn.then_some((self.turn * 2, dir.reverse())))
+            .map(|(cost, dir)| (cost + self.step, dir))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Instance {
     pub score: i32,
@@ -112,60 +169,18 @@ pub fn parse_input(input: &str) -> (Vec<&[u8]>, IVec2) {
     (map, pos)
 }
 
-pub fn solution_part_1(map: &[&[u8]], pos: IVec2) -> i32 {
-    std::iter::repeat(())
-        .fold_while(
-            (
-                BinaryHeap::from([Instance::new(0, pos, Dir::X)]),
-                HashSet::new(),
-                None,
-            ),
-            |(mut queue, mut visited, score), _| {
-                let curr = queue.pop().unwrap();
-
-                if visited.contains(&curr.pos) {
-                    return FoldWhile::Continue((queue, visited, score));
-                }
-
-                if map.at(curr.pos) == b'E' {
-                    return FoldWhile::Done((queue, visited, Some(curr.score)));
-                }
-
-                let neighbors = curr
-                    .dir
-                    .turns()
-                    .into_iter()
-                    .map(|dir| (1000, dir))
-                    .chain(std::iter::once((0, curr.dir)))
-                    .map(|(score, dir)| (score + 1, dir, IVec2::from(dir)))
-                    .flat_map(|(score, dir, delta)| {
-                        let next_pos = curr.pos + delta;
-                        let next_score = curr.score + score;
+pub type Visited = HashMap<(IVec2, Dir), (i32, Vec<(IVec2, Dir)>)>;
 
-                        if map.at(next_pos) == b'#' {
-                            return None;
-                        }
+/// Runs Dijkstra over every `(pos, dir)` state, returns the best score and predecessors of each
+/// state along with the end position.
+pub fn explore(map: &[&[u8]], pos: IVec2, costs: &MazeCosts) -> (Visited, IVec2) {
+    costs.validate();
 
-                        Some(Instance::new(next_score, next_pos, dir))
-                    });
-
-                queue.extend(neighbors);
-                visited.insert(curr.pos);
-
-                FoldWhile::Continue((queue, visited, score))
-            },
-        )
-        .into_inner()
-        .2
-        .unwrap()
-}
-
-pub fn solution_part_2(map: &[&[u8]], pos: IVec2) -> i32 {
     let (_, visited, end) = std::iter::repeat(())
         .fold_while(
             (
-                BinaryHeap::from([Instance::new(0, pos, Dir::X)]),
-                HashMap::<(IVec2, Dir), (i32, Vec<(IVec2, Dir)>)>::new(),
+                BinaryHeap::from([Instance::new(0, pos, costs.start_dir)]),
+                Visited::new(),
                 None,
             ),
             |(mut queue, mut visited, mut end), _| {
@@ -182,13 +197,9 @@ pub fn solution_part_2(map: &[&[u8]], pos: IVec2) -> i32 {
                 }
 
                 if map.at(curr.pos) != b'E' {
-                    let neighbors = curr
-                        .dir
-                        .turns()
-                        .into_iter()
-                        .map(|dir| (1000, dir))
-                        .chain(std::iter::once((0, curr.dir)))
-                        .map(|(score, dir)| (score + 1, dir, IVec2::from(dir)))
+                    let neighbors = costs
+                        .moves(curr.dir)
+                        .map(|(score, dir)| (score, dir, IVec2::from(dir)))
                         .flat_map(|(score, dir, delta)| {
                             let next_pos = curr.pos + delta;
                             let next_score = curr.score + score;
@@ -223,26 +234,38 @@ pub fn solution_part_2(map: &[&[u8]], pos: IVec2) -> i32 {
         )
         .into_inner();
 
-    let backtrack_start = [Dir::X, Dir::NegY].map(|dir| (end.unwrap(), dir)).to_vec();
-
-    let min_score = backtrack_start
-        .iter()
-        .map(|&(pos, dir)| visited.get(&(pos, dir)).unwrap().0)
-        .min()
-        .unwrap();
+    (visited, end.unwrap())
+}
 
-    let backtrack_start = backtrack_start
+/// Returns the end states reached with the minimum score.
+pub fn end_states(visited: &Visited, end: IVec2) -> Vec<(IVec2, Dir)> {
+    let end_states = [Dir::X, Dir::Y, Dir::NegX, Dir::NegY]
         .into_iter()
-        .filter(|&(pos, dir)| visited.get(&(pos, dir)).unwrap().0 == min_score)
+        .flat_map(|dir| {
+            visited
+                .get(&(end, dir))
+                .map(|&(score, _)| ((end, dir), score))
+        })
         .collect_vec();
 
+    let min_score = end_states.iter().map(|&(_, score)| score).min().unwrap();
+
+    end_states
+        .into_iter()
+        .filter(|&(_, score)| score == min_score)
+        .map(|(state, _)| state)
+        .collect_vec()
+}
+
+/// Returns every state lying on any optimal path ending in `end_states`.
+pub fn best_states(visited: &Visited, end_states: &[(IVec2, Dir)]) -> HashSet<(IVec2, Dir)> {
     std::iter::repeat(())
         .fold_while(
             (
-                HashSet::from_iter(backtrack_start),
-                HashSet::from([end.unwrap()]),
+                HashSet::from_iter(end_states.iter().copied()),
+                HashSet::from_iter(end_states.iter().copied()),
             ),
-            |(next, paths), _| {
+            |(next, states), _| {
                 let next: HashSet<_> = next
                     .into_iter()
                     .flat_map(|(pos, dir)| visited.get(&(pos, dir)))
@@ -251,27 +274,98 @@ pub fn solution_part_2(map: &[&[u8]], pos: IVec2) -> i32 {
                     .collect();
 
                 if next.is_empty() {
-                    return FoldWhile::Done((next, paths));
+                    return FoldWhile::Done((next, states));
                 }
 
-                let paths = paths
-                    .union(&next.iter().map(|&(pos, _)| pos).collect())
-                    .copied()
-                    .collect();
+                let states = states.union(&next).copied().collect();
 
-                FoldWhile::Continue((next, paths))
+                FoldWhile::Continue((next, states))
             },
         )
         .into_inner()
         .1
-        .len() as i32
+}
+
+#[derive(Debug, Clone)]
+pub struct MazeSolution {
+    /// Minimum score from the start to the end.
+    pub score: i32,
+    /// One optimal path as `(pos, dir)` states, from the start to the end.
+    pub path: Vec<(IVec2, Dir)>,
+    /// Tiles lying on any optimal path.
+    pub tiles: HashSet<IVec2>,
+    /// Number of distinct optimal paths.
+    pub path_count: u64,
+    /// States lying on any optimal path.
+    pub states: HashSet<(IVec2, Dir)>,
+    /// Best score and predecessors of every explored state.
+    pub visited: Visited,
+}
+
+impl MazeSolution {
+    /// Returns the tiles where an optimal path turns.
+    pub fn turn_points(&self) -> HashSet<IVec2> {
+        self.states
+            .iter()
+            .flat_map(|state| self.visited.get(state).map(|(_, prevs)| (state.1, prevs)))
+            .flat_map(|(dir, prevs)| {
+                prevs
+                    .iter()
+                    .filter(move |&&(_, prev)| prev != dir)
+                    .map(|&(pos, _)| pos)
+            })
+            .collect()
+    }
+}
+
+pub fn solve(map: &[&[u8]], pos: IVec2, costs: &MazeCosts) -> MazeSolution {
+    let (visited, end) = explore(map, pos, costs);
+    let end_states = end_states(&visited, end);
+    let states = best_states(&visited, &end_states);
+
+    let counts = states.iter().sorted_by_key(|state| visited[state].0).fold(
+        HashMap::new(),
+        |mut counts, &state| {
+            let count = match visited[&state].1.as_slice() {
+                [] => 1,
+                prevs => prevs.iter().map(|prev| counts[prev]).sum(),
+            };
+
+            counts.insert(state, count);
+            counts
+        },
+    );
+
+    let mut path = std::iter::successors(Some(end_states[0]), |state| {
+        visited[state].1.first().copied()
+    })
+    .collect_vec();
+    path.reverse();
+
+    MazeSolution {
+        score: visited[&end_states[0]].0,
+        path,
+        tiles: states.iter().map(|&(pos, _)| pos).collect(),
+        path_count: end_states.iter().map(|state| counts[state]).sum(),
+        states,
+        visited,
+    }
+}
+
+pub fn solution_part_1(map: &[&[u8]], pos: IVec2, costs: &MazeCosts) -> i32 {
+    solve(map, pos, costs).score
+}
+
+pub fn solution_part_2(map: &[&[u8]], pos: IVec2, costs: &MazeCosts) -> i32 {
+    solve(map, pos, costs).tiles.len() as i32
 }
 
 fn main() {
     let input = input(16);
 
     let (map, pos) = parse_input(&input);
+    let costs = MazeCosts::default();
 
-    println!("Part 1 solution: {}", solution_part_1(&map, pos));
-    println!("Part 2 solution: {}", solution_part_2(&map, pos));
+    println!("Part 1 solution: {}", solution_part_1(&map, pos, &costs));
+    println!("Part 2 solution: {}", solution_part_2(&map, pos, &costs));
 }
@@ -0,0 +1,305 @@
+use std::collections::{HashMap, HashSet};
+
+use aoc_2024::input;
+use crossterm::{
+    event::{self, Event, KeyCode, MouseEventKind},
+    terminal::{disable_raw_mode, enable_raw_mode},
+    ExecutableCommand,
+};
+use glam::*;
+use itertools::Itertools;
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    style::Color,
+    symbols::Marker,
+    widgets::{
+        canvas::{Canvas, Points},
+        Block,
+    },
+};
+
+#[path = "16.rs"]
+#[allow(dead_code)]
+mod day;
+
+use day::MapAt;
+
+const FRONTIER_WIDTH: i32 = 1001;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Tile {
+    Wall,
+    Open,
+    Explored,
+    Frontier,
+    Path,
+    Turn,
+    Endpoint,
+}
+
+impl Tile {
+    fn ansi(self) -> &'static str {
+        match self {
+            Tile::Wall => "\x1b[90m#",
+            Tile::Open => "\x1b[0m.",
+            Tile::Explored => "\x1b[34m.",
+            Tile::Frontier => "\x1b[36m*",
+            Tile::Path => "\x1b[32mO",
+            Tile::Turn => "\x1b[33m+",
+            Tile::Endpoint => "\x1b[31m@",
+        }
+    }
+
+    fn rgb(self) -> [u8; 3] {
+        match self {
+            Tile::Wall => [64, 64, 64],
+            Tile::Open => [0, 0, 0],
+            Tile::Explored => [32, 64, 160],
+            Tile::Frontier => [0, 200, 200],
+            Tile::Path => [0, 200, 0],
+            Tile::Turn => [230, 200, 0],
+            Tile::Endpoint => [220, 0, 0],
+        }
+    }
+
+    fn color(self) -> Color {
+        let [r, g, b] = self.rgb();
+        Color::Rgb(r, g, b)
+    }
+}
+
+struct Maze<'a> {
+    map: Vec<&'a [u8]>,
+    scores: HashMap<IVec2, i32>,
+    paths: HashSet<IVec2>,
+    turns: HashSet<IVec2>,
+    end_score: i32,
+    max_score: i32,
+}
+
+impl<'a> Maze<'a> {
+    fn new(map: Vec<&'a [u8]>, pos: IVec2) -> Self {
+        let solution = day::solve(&map, pos, &day::MazeCosts::default());
+
+        let scores = solution
+            .visited
+            .iter()
+            .map(|(&(pos, _), &(score, _))| (pos, score))
+            .into_grouping_map()
+            .min();
+
+        Self {
+            turns: solution.turn_points(),
+            end_score: solution.score,
+            paths: solution.tiles,
+            max_score: scores.values().copied().max().unwrap_or(0),
+            scores,
+            map,
+        }
+    }
+
+    fn size(&self) -> IVec2 {
+        ivec2(self.map[0].len() as i32, self.map.len() as i32)
+    }
+
+    /// Returns the tile at `pos` with the search expanded up to `score`.
+    fn tile(&self, pos: IVec2, score: i32) -> Tile {
+        let is_found = score >= self.end_score;
+        let is_done = score >= self.max_score;
+
+        match (self.map.as_slice().at(pos), self.scores.get(&pos)) {
+            (b'#', _) => Tile::Wall,
+            (b'S' | b'E', _) => Tile::Endpoint,
+            _ if is_found && self.turns.contains(&pos) => Tile::Turn,
+            _ if is_found && self.paths.contains(&pos) => Tile::Path,
+            (_, Some(&s)) if s <= score && s > score - FRONTIER_WIDTH && !is_done => Tile::Frontier,
+            (_, Some(&s)) if s <= score => Tile::Explored,
+            _ => Tile::Open,
+        }
+    }
+
+    fn tiles(&self, score: i32) -> impl Iterator<Item = (IVec2, Tile)> + '_ {
+        let size = self.size();
+
+        (0..size.y)
+            .cartesian_product(0..size.x)
+            .map(move |(y, x)| (ivec2(x, y), self.tile(ivec2(x, y), score)))
+    }
+
+    fn ansi(&self) -> String {
+        self.tiles(self.max_score)
+            .chunks(self.size().x as usize)
+            .into_iter()
+            .map(|row| row.map(|(_, tile)| tile.ansi()).join("") + "\x1b[0m")
+            .join("\n")
+    }
+
+    fn ppm(&self, scale: i32) -> Vec<u8> {
+        let size = self.size() * scale;
+        let tiles = self.tiles(self.max_score).collect::<HashMap<_, _>>();
+
+        format!("P6\n{} {}\n255\n", size.x, size.y)
+            .into_bytes()
+            .into_iter()
+            .chain(
+                (0..size.y)
+                    .cartesian_product(0..size.x)
+                    .flat_map(|(y, x)| tiles[&(ivec2(x, y) / scale)].rgb()),
+            )
+            .collect()
+    }
+}
+
+struct State {
+    duration: std::time::Duration,
+    time: std::time::Instant,
+    elapsed: std::time::Duration,
+}
+
+impl State {
+    fn new() -> Self {
+        Self {
+            duration: std::time::Duration::from_secs(10),
+            time: std::time::Instant::now(),
+            elapsed: std::time::Duration::default(),
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    log::info!("Running day 16 search");
+
+    let input = input(16);
+    let (map, pos) = day::parse_input(&input);
+    let maze = Maze::new(map, pos);
+
+    match std::env::args().nth(1).as_deref() {
+        Some("ansi") => {
+            println!("{}", maze.ansi());
+            return;
+        }
+        Some("ppm") => {
+            let path = std::env::args().nth(2).unwrap_or("16.ppm".to_string());
+            std::fs::write(&path, maze.ppm(4)).expect("image should be written");
+            println!("Written to {path}");
+            return;
+        }
+        _ => {}
+    }
+
+    log::info!("Starting terminal");
+
+    std::io::stdout()
+        .execute(crossterm::event::EnableMouseCapture)
+        .expect("mouse should be captured");
+    enable_raw_mode().expect("raw mode should be enabled");
+
+    let mut terminal = ratatui::init();
+    terminal.clear().expect("terminal should be cleared");
+
+    let size = maze.size();
+    let term_size = (size / ivec2(1, 2) + ivec2(2, 2)).as_u16vec2();
+
+    let mut anim_state = State::new();
+    let mut speed: i32 = 10; // per decisecond
+    let mut is_running = true;
+
+    while is_running {
+        let speed_secs = speed as f32 / 10.0;
+
+        let dt = anim_state.time.elapsed().mul_f32(speed_secs);
+        anim_state.time = std::time::Instant::now();
+        anim_state.elapsed = (anim_state.elapsed + dt).min(anim_state.duration);
+
+        let progress = anim_state.elapsed.as_secs_f32() / anim_state.duration.as_secs_f32();
+        let score = (maze.max_score as f32 * progress) as i32;
+
+        let points = maze
+            .tiles(score)
+            .filter(|&(_, tile)| tile != Tile::Open)
+            .into_group_map_by(|&(_, tile)| tile);
+
+        terminal
+            .draw(|frame| {
+                let [center] = Layout::horizontal([Constraint::Length(term_size.x)])
+                    .flex(Flex::Center)
+                    .areas(frame.area());
+                let [center] = Layout::vertical([Constraint::Length(term_size.y)])
+                    .flex(Flex::Center)
+                    .areas(center);
+
+                let canvas = Canvas::default()
+                    .block(
+                        Block::bordered()
+                            .title_top("Advent of Code 2024 - Day 16 Reindeer Maze")
+                            .title_bottom(format!(
+                                "Score: {} / {} | Speed: {:.1}x | Space to restart, Q to quit",
+                                score, maze.max_score, speed_secs,
+                            )),
+                    )
+                    .marker(Marker::HalfBlock)
+                    .x_bounds([0.0, size.x as f64])
+                    .y_bounds([0.0, size.y as f64])
+                    .paint(|ctx| {
+                        points.iter().for_each(|(tile, points)| {
+                            ctx.draw(&Points {
+                                coords: &points
+                                    .iter()
+                                    .map(|(pos, _)| (pos.x as f64, (size.y - pos.y) as f64))
+                                    .collect_vec(),
+                                color: tile.color(),
+                            });
+                        });
+                    });
+
+                frame.render_widget(canvas, center);
+            })
+            .expect("frame should be rendered");
+
+        while event::poll(std::time::Duration::from_millis(10)).expect("event should be polled") {
+            match event::read().expect("event should be read") {
+                Event::Key(event) => match event.code {
+                    KeyCode::Char(' ') => {
+                        anim_state = State::new();
+                    }
+                    KeyCode::Char('q') => {
+                        is_running = false;
+                        break;
+                    }
+                    _ => {}
+                },
+                Event::Mouse(event) => match event.kind {
+                    MouseEventKind::ScrollUp => {
+                        let speed_delta = match speed {
+                            0..10 => 1,
+                            10..100 => 10,
+                            100..1000 => 100,
+                            _ => 0,
+                        };
+                        speed = (speed + speed_delta).min(1000);
+                    }
+                    MouseEventKind::ScrollDown => {
+                        let speed_delta = match speed {
+                            0..=10 => 1,
+                            11..=100 => 10,
+                            101..=1000 => 100,
+                            _ => 0,
+                        };
+                        speed = (speed - speed_delta).max(0);
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+
+    ratatui::restore();
+
+    disable_raw_mode().expect("raw mode should be disabled");
+    std::io::stdout()
+        .execute(crossterm::event::DisableMouseCapture)
+        .expect("mouse should be released");
+}
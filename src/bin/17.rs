@@ -1,8 +1,8 @@
-use core::panic;
+use std::collections::{HashMap, HashSet};
 
 use aoc_2024::input;
 use glam::*;
-use itertools::{FoldWhile, Itertools};
+use itertools::Itertools;
 use nom::{
     bytes::complete::{tag, take_while_m_n},
     character::complete::{digit1, multispace1},
@@ -12,6 +12,54 @@ use nom::{
     IResult,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmError {
+    StepLimit(usize),
+    InvalidOpCode {
+        ptr: usize,
+        opcode: i64,
+    },
+    InvalidOperand {
+        ptr: usize,
+        operand: i64,
+    },
+    OddJumpTarget {
+        ptr: usize,
+        target: i64,
+    },
+    ShiftOutOfRange {
+        ptr: usize,
+        shift: i64,
+    },
+    /// The same pointer and registers were reached twice, so the program never halts.
+    Loop {
+        ptr: usize,
+    },
+}
+
+impl std::fmt::Display for VmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StepLimit(steps) => write!(f, "Step limit of {steps} reached"),
+            Self::InvalidOpCode { ptr, opcode } => {
+                write!(f, "Invalid opcode {opcode} at {}", ptr * 2)
+            }
+            Self::InvalidOperand { ptr, operand } => {
+                write!(f, "Invalid combo operand {operand} at {}", ptr * 2)
+            }
+            Self::OddJumpTarget { ptr, target } => {
+                write!(f, "Odd jump target {target} at {}", ptr * 2)
+            }
+            Self::ShiftOutOfRange { ptr, shift } => {
+                write!(f, "Shift amount {shift} out of range at {}", ptr * 2)
+            }
+            Self::Loop { ptr } => write!(f, "Infinite loop detected at {}", ptr * 2),
+        }
+    }
+}
+
+impl std::error::Error for VmError {}
+
 #[derive(Debug, Clone, Copy)]
 pub enum OpCode {
     Adv,
@@ -22,6 +70,7 @@ pub enum OpCode {
     Out,
     Bdv,
     Cdv,
+    Invalid(i64),
 }
 
 impl From<i64> for OpCode {
@@ -35,15 +84,89 @@ impl From<i64> for OpCode {
             5 => Self::Out,
             6 => Self::Bdv,
             7 => Self::Cdv,
-            _ => panic!("Invalid opcode: {}", value),
+            _ => Self::Invalid(value),
+        }
+    }
+}
+
+impl OpCode {
+    pub const ALL: [Self; 8] = [
+        Self::Adv,
+        Self::Bxl,
+        Self::Bst,
+        Self::Jnz,
+        Self::Bxc,
+        Self::Out,
+        Self::Bdv,
+        Self::Cdv,
+    ];
+
+    pub fn code(&self) -> i64 {
+        match self {
+            Self::Adv => 0,
+            Self::Bxl => 1,
+            Self::Bst => 2,
+            Self::Jnz => 3,
+            Self::Bxc => 4,
+            Self::Out => 5,
+            Self::Bdv => 6,
+            Self::Cdv => 7,
+            Self::Invalid(value) => *value,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Adv => "adv",
+            Self::Bxl => "bxl",
+            Self::Bst => "bst",
+            Self::Jnz => "jnz",
+            Self::Bxc => "bxc",
+            Self::Out => "out",
+            Self::Bdv => "bdv",
+            Self::Cdv => "cdv",
+            Self::Invalid(_) => "???",
+        }
+    }
+
+    /// Returns the instruction as assembly, e.g. `bst A` or `bxl 5`.
+    pub fn disassemble(&self, operand: &Operand) -> String {
+        match self {
+            Self::Bxl | Self::Jnz => format!("{} {}", self.mnemonic(), operand.lit),
+            Self::Bxc if operand.lit == 0 => self.mnemonic().to_string(),
+            Self::Bxc => format!("{} {}", self.mnemonic(), operand.lit),
+            Self::Invalid(value) => format!("{} {value},{}", self.mnemonic(), operand.lit),
+            _ => format!("{} {}", self.mnemonic(), operand.combo),
+        }
+    }
+
+    /// Returns what the instruction does, e.g. `A = A >> 3`.
+    pub fn annotate(&self, operand: &Operand) -> String {
+        let (lit, combo) = (operand.lit, operand.combo);
+
+        match self {
+            Self::Adv | Self::Bst | Self::Out | Self::Bdv | Self::Cdv
+                if matches!(combo, ComboOperand::Reserved(_)) =>
+            {
+                "invalid combo operand".to_string()
+            }
+            Self::Adv => format!("A = A >> {combo}"),
+            Self::Bxl => format!("B = B ^ {lit}"),
+            Self::Bst => format!("B = {combo} & 7"),
+            Self::Jnz => format!("if A != 0 goto {lit}"),
+            Self::Bxc => "B = B ^ C".to_string(),
+            Self::Out => format!("out {combo} & 7"),
+            Self::Bdv => format!("B = A >> {combo}"),
+            Self::Cdv => format!("C = A >> {combo}"),
+            Self::Invalid(_) => "invalid opcode".to_string(),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Operand {
-    lit: i64,
-    combo: ComboOperand,
+    pub lit: i64,
+    pub combo: ComboOperand,
 }
 
 impl From<i64> for Operand {
@@ -59,6 +182,8 @@ impl From<i64> for Operand {
 pub enum ComboOperand {
     Lit(i64),
     Reg(usize),
+    /// Operand 7 is reserved and will not appear in valid programs.
+    Reserved(i64),
 }
 
 impl From<i64> for ComboOperand {
@@ -66,11 +191,65 @@ impl From<i64> for ComboOperand {
         match value {
             0..=3 => Self::Lit(value),
             register @ 4..=6 => Self::Reg(register as usize - 4),
-            _ => panic!("Invalid operand: {}", value),
+            _ => Self::Reserved(value),
         }
     }
 }
 
+impl std::fmt::Display for ComboOperand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lit(value) | Self::Reserved(value) => write!(f, "{value}"),
+            Self::Reg(register) => write!(f, "{}", ["A", "B", "C"][*register]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TraceStep {
+    pub ptr: usize,
+    pub opcode: OpCode,
+    pub lit: i64,
+    /// Value of the combo operand, or `None` if it is reserved.
+    pub combo: Option<i64>,
+    pub before: [i64; 3],
+    pub after: [i64; 3],
+    pub out: Option<i64>,
+}
+
+impl TraceStep {
+    pub const CSV_HEADER: &str = "addr,opcode,lit,combo,a,b,c,next_a,next_b,next_c,out";
+
+    pub fn to_csv(self) -> String {
+        [
+            (self.ptr * 2).to_string(),
+            self.opcode.mnemonic().to_string(),
+            self.lit.to_string(),
+            self.combo.map_or(String::new(), |value| value.to_string()),
+        ]
+        .into_iter()
+        .chain(self.before.iter().chain(&self.after).map(i64::to_string))
+        .chain([self.out.map_or(String::new(), |value| value.to_string())])
+        .join(",")
+    }
+
+    pub fn to_json(self) -> String {
+        let option =
+            |value: Option<i64>| value.map_or("null".to_string(), |value| value.to_string());
+
+        format!(
+            r#"{{"addr":{},"opcode":"{}","lit":{},"combo":{},"before":[{}],"after":[{}],"out":{}}}"#,
+            self.ptr * 2,
+            self.opcode.mnemonic(),
+            self.lit,
+            option(self.combo),
+            self.before.iter().join(","),
+            self.after.iter().join(","),
+            option(self.out),
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Computer {
     pub registers: [i64; 3],
@@ -78,46 +257,200 @@ pub struct Computer {
 }
 
 impl Computer {
-    pub fn compute(&self) -> Vec<i64> {
-        std::iter::repeat(())
-            .fold_while(
-                (0, self.registers, Vec::new()),
-                |(mut ptr, mut registers, mut out), _| {
-                    if ptr >= self.program.len() {
-                        return FoldWhile::Done((ptr, registers, out));
-                    }
+    /// Executes the instruction at `ptr`, returns the next pointer and the output if any.
+    pub fn step(
+        &self,
+        ptr: usize,
+        registers: &mut [i64; 3],
+    ) -> Result<(usize, Option<i64>), VmError> {
+        let (opcode, operand) = self.program.get(ptr).unwrap();
+
+        let lit_val = operand.lit;
+        let combo_val = match operand.combo {
+            ComboOperand::Lit(value) => Ok(value),
+            ComboOperand::Reg(register) => Ok(registers[register]),
+            ComboOperand::Reserved(operand) => Err(VmError::InvalidOperand { ptr, operand }),
+        };
+        let shift_val = combo_val.and_then(|shift| match shift {
+            0..64 => Ok(shift),
+            _ => Err(VmError::ShiftOutOfRange { ptr, shift }),
+        });
+
+        match opcode {
+            OpCode::Adv => registers[0] >>= shift_val?,
+            OpCode::Bxl => registers[1] ^= lit_val,
+            OpCode::Bst => registers[1] = combo_val? & 0b111,
+            OpCode::Jnz => match (registers[0], lit_val) {
+                (0, _) => {}
+                (_, target) if target % 2 == 0 => return Ok((target as usize / 2, None)),
+                (_, target) => return Err(VmError::OddJumpTarget { ptr, target }),
+            },
+            OpCode::Bxc => registers[1] ^= registers[2],
+            OpCode::Out => return Ok((ptr + 1, Some(combo_val? & 0b111))),
+            OpCode::Bdv => registers[1] = registers[0] >> shift_val?,
+            OpCode::Cdv => registers[2] = registers[0] >> shift_val?,
+            OpCode::Invalid(opcode) => {
+                return Err(VmError::InvalidOpCode {
+                    ptr,
+                    opcode: *opcode,
+                })
+            }
+        }
 
-                    let (opcode, operand) = self.program.get(ptr).unwrap();
-                    ptr += 1;
+        Ok((ptr + 1, None))
+    }
 
-                    let lit_val = operand.lit;
-                    let combo_val = match operand.combo {
-                        ComboOperand::Lit(value) => value,
-                        ComboOperand::Reg(register) => registers[register],
-                    };
+    pub fn compute(&self) -> Vec<i64> {
+        self.compute_with_limits(usize::MAX).unwrap()
+    }
 
-                    match opcode {
-                        OpCode::Adv => registers[0] >>= combo_val,
-                        OpCode::Bxl => registers[1] ^= lit_val,
-                        OpCode::Bst => registers[1] = combo_val & 0b111,
-                        OpCode::Jnz => match registers[0] {
-                            0 => {}
-                            _ => ptr = lit_val as usize / 2,
+    /// Returns a record of every instruction executed until the program halts or fails.
+    pub fn trace(&self) -> impl Iterator<Item = Result<TraceStep, VmError>> + '_ {
+        let (mut ptr, mut registers, mut is_done) = (0, self.registers, false);
+
+        std::iter::from_fn(move || {
+            if is_done || ptr >= self.program.len() {
+                return None;
+            }
+
+            let (opcode, operand) = &self.program[ptr];
+            let before = registers;
+
+            match self.step(ptr, &mut registers) {
+                Ok((next, out)) => {
+                    let step = TraceStep {
+                        ptr,
+                        opcode: *opcode,
+                        lit: operand.lit,
+                        combo: match operand.combo {
+                            ComboOperand::Lit(value) => Some(value),
+                            ComboOperand::Reg(register) => Some(before[register]),
+                            ComboOperand::Reserved(_) => None,
                         },
-                        OpCode::Bxc => registers[1] ^= registers[2],
-                        OpCode::Out => out.push(combo_val & 0b111),
-                        OpCode::Bdv => registers[1] = registers[0] >> combo_val,
-                        OpCode::Cdv => registers[2] = registers[0] >> combo_val,
-                    }
+                        before,
+                        after: registers,
+                        out,
+                    };
+                    ptr = next;
+                    Some(Ok(step))
+                }
+                Err(err) => {
+                    is_done = true;
+                    Some(Err(err))
+                }
+            }
+        })
+    }
 
-                    FoldWhile::Continue((ptr, registers, out))
-                },
-            )
-            .into_inner()
-            .2
+    /// Runs the program for at most `max_steps` instructions.
+    pub fn compute_with_limits(&self, max_steps: usize) -> Result<Vec<i64>, VmError> {
+        let (mut ptr, mut registers, mut out) = (0, self.registers, Vec::new());
+        let mut states = HashSet::new();
+
+        for _ in 0..max_steps {
+            if ptr >= self.program.len() {
+                return Ok(out);
+            }
+
+            if !states.insert((ptr, registers)) {
+                return Err(VmError::Loop { ptr });
+            }
+
+            let (next, value) = self.step(ptr, &mut registers)?;
+            ptr = next;
+            out.extend(value);
+        }
+
+        match ptr >= self.program.len() {
+            true => Ok(out),
+            false => Err(VmError::StepLimit(max_steps)),
+        }
     }
 }
 
+/// Returns the program as one instruction per line, prefixed with its address.
+pub fn disassemble(program: &[(OpCode, Operand)]) -> String {
+    program
+        .iter()
+        .enumerate()
+        .map(|(ptr, (opcode, operand))| {
+            format!(
+                "{:>2}: {:<6} ; {}",
+                ptr * 2,
+                opcode.disassemble(operand),
+                opcode.annotate(operand)
+            )
+        })
+        .join("\n")
+}
+
+/// Turns assembly back into the comma-separated program.
+///
+/// Each line holds one instruction, optionally prefixed with an address and followed by a `;`
+/// comment, as produced by [`disassemble`]. Anything [`disassemble`] emits assembles back to the
+/// same program, including reserved combo operands and invalid opcodes.
+pub fn assemble(source: &str) -> Result<String, String> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split(';').next().unwrap().trim()))
+        .map(|(i, line)| {
+            (
+                i,
+                line.split_once(':').map_or(line, |(_, line)| line).trim(),
+            )
+        })
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            let (mnemonic, operand) = line.split_once(' ').unwrap_or((line, ""));
+
+            let invalid_operand =
+                || format!("Line {i}: invalid operand `{operand}` for `{mnemonic}`");
+
+            // Invalid opcodes are disassembled as `??? <opcode>,<operand>`.
+            if mnemonic == OpCode::Invalid(0).mnemonic() {
+                return operand
+                    .trim()
+                    .split_once(',')
+                    .and_then(|(opcode, operand)| {
+                        Some((
+                            opcode.trim().parse::<i64>().ok()?,
+                            operand.trim().parse::<i64>().ok()?,
+                        ))
+                    })
+                    .filter(|&(opcode, operand)| {
+                        matches!(OpCode::from(opcode), OpCode::Invalid(_)) && operand >= 0
+                    })
+                    .map(|(opcode, operand)| format!("{opcode},{operand}"))
+                    .ok_or_else(invalid_operand);
+            }
+
+            let opcode = OpCode::ALL
+                .into_iter()
+                .find(|opcode| opcode.mnemonic() == mnemonic.to_lowercase())
+                .ok_or(format!("Line {i}: unknown mnemonic `{mnemonic}`"))?;
+
+            // Combo operands 4 to 6 are written as registers, all other values as is.
+            let operand = match (opcode, operand.trim()) {
+                (OpCode::Bxc, "") => Some(0),
+                (OpCode::Bxl | OpCode::Jnz | OpCode::Bxc, operand) => operand.parse::<i64>().ok(),
+                (_, "A" | "a") => Some(4),
+                (_, "B" | "b") => Some(5),
+                (_, "C" | "c") => Some(6),
+                (_, operand) => operand
+                    .parse()
+                    .ok()
+                    .filter(|value| !(4..=6).contains(value)),
+            }
+            .filter(|&value| value >= 0)
+            .ok_or_else(invalid_operand)?;
+
+            Ok(format!("{},{}", opcode.code(), operand))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|program| program.join(","))
+}
+
 pub fn parse_input(input: &str) -> (Computer, Vec<i32>) {
     fn i32(input: &str) -> IResult<&str, i32> {
         let (input, n) = recognize(digit1)(input)?;
@@ -190,30 +523,415 @@ pub fn solution_part_1(computer: &Computer) -> String {
         .join(",")
 }
 
-pub fn solution_part_2(computer: &Computer, src_code: &[i32]) -> i64 {
-    src_code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopShape {
+    /// Number of bits shifted out of A per iteration.
+    pub shift: i64,
+    /// Number of low bits of A the output of an iteration depends on.
+    pub window: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuineError {
+    /// The program does not end with the only jump, `jnz 0`, looping over the whole program.
+    NoLoop,
+    /// A is not shifted right exactly once per iteration by a constant.
+    NonConstantShift,
+    /// The loop does not output exactly one value per iteration.
+    OutputCount(usize),
+    /// The register is read before it is written, so iterations are not independent.
+    CarriedRegister(char),
+    /// The output depends on an unbounded number of bits of A.
+    UnboundedWindow,
+    Vm(VmError),
+    NoSolution,
+}
+
+impl std::fmt::Display for QuineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoLoop => write!(f, "program should end with its only jump, `jnz 0`"),
+            Self::NonConstantShift => {
+                write!(
+                    f,
+                    "A should be shifted by a constant exactly once per iteration"
+                )
+            }
+            Self::OutputCount(count) => {
+                write!(f, "loop should output exactly once, found {count} outputs")
+            }
+            Self::CarriedRegister(register) => {
+                write!(f, "register {register} is carried across iterations")
+            }
+            Self::UnboundedWindow => write!(f, "output depends on unbounded bits of A"),
+            Self::Vm(err) => write!(f, "{err}"),
+            Self::NoSolution => write!(f, "no value of A reproduces the program"),
+        }
+    }
+}
+
+impl std::error::Error for QuineError {}
+
+impl From<VmError> for QuineError {
+    fn from(err: VmError) -> Self {
+        Self::Vm(err)
+    }
+}
+
+/// Checks the program is a single loop consuming a constant number of bits of A per iteration
+/// and outputting once, and returns its shape.
+pub fn analyse_loop(program: &[(OpCode, Operand)]) -> Result<LoopShape, QuineError> {
+    let jumps = program
         .iter()
-        .map(|&code| code as i64)
-        .rev()
-        .fold(vec![0], |inits: Vec<i64>, code| {
-            inits
-                .into_iter()
-                .flat_map(|init| {
-                    (0..8).map(move |x| (init << 3) | x).filter(|&x| {
-                        let out = Computer {
-                            registers: [x, 0, 0],
-                            program: computer.program.clone(),
+        .positions(|(opcode, _)| matches!(opcode, OpCode::Jnz))
+        .collect_vec();
+
+    match (jumps.as_slice(), program.last()) {
+        ([jump], Some((_, operand))) if *jump == program.len() - 1 && operand.lit == 0 => {}
+        _ => return Err(QuineError::NoLoop),
+    }
+
+    let body = &program[..program.len() - 1];
+
+    let shift = match body
+        .iter()
+        .filter(|(opcode, _)| matches!(opcode, OpCode::Adv))
+        .collect_vec()
+        .as_slice()
+    {
+        [(_, operand)] => match operand.combo {
+            ComboOperand::Lit(shift @ 1..) => shift,
+            _ => return Err(QuineError::NonConstantShift),
+        },
+        _ => return Err(QuineError::NonConstantShift),
+    };
+
+    match body
+        .iter()
+        .filter(|(opcode, _)| matches!(opcode, OpCode::Out))
+        .count()
+    {
+        1 => {}
+        count => return Err(QuineError::OutputCount(count)),
+    }
+
+    let combo_reg = |operand: &Operand| match operand.combo {
+        ComboOperand::Reg(register) => Some(register),
+        _ => None,
+    };
+
+    // B and C must be written before they are read, so each iteration only depends on A
+    body.iter()
+        .try_fold([true, false, false], |mut written, (opcode, operand)| {
+            let (reads, write) = match opcode {
+                OpCode::Adv => ([Some(0), combo_reg(operand)], None),
+                OpCode::Bxl => ([Some(1), None], Some(1)),
+                OpCode::Bst => ([combo_reg(operand), None], Some(1)),
+                OpCode::Bxc => ([Some(1), Some(2)], Some(1)),
+                OpCode::Out => ([combo_reg(operand), None], None),
+                OpCode::Bdv => ([Some(0), combo_reg(operand)], Some(1)),
+                OpCode::Cdv => ([Some(0), combo_reg(operand)], Some(2)),
+                OpCode::Jnz | OpCode::Invalid(_) => ([None, None], None),
+            };
+
+            if let Some(register) = reads.into_iter().flatten().find(|&r| !written[r]) {
+                return Err(QuineError::CarriedRegister(['A', 'B', 'C'][register]));
+            }
+
+            if let Some(register) = write {
+                written[register] = true;
+            }
+
+            Ok(written)
+        })?;
+
+    // Upper bound of the bit width of each register before each instruction
+    let bits = body
+        .iter()
+        .scan([64u32; 3], |bits, (opcode, operand)| {
+            let before = *bits;
+
+            match opcode {
+                OpCode::Bxl => bits[1] = bits[1].max(64 - operand.lit.leading_zeros()),
+                OpCode::Bst => bits[1] = 3,
+                OpCode::Bxc => bits[1] = bits[1].max(bits[2]),
+                OpCode::Bdv => bits[1] = 64,
+                OpCode::Cdv => bits[2] = 64,
+                _ => {}
+            }
+
+            Some(before)
+        })
+        .collect_vec();
+
+    // Number of low bits of each register the output depends on, walking backwards
+    let needed =
+        body.iter()
+            .zip(bits)
+            .rev()
+            .fold([0i64; 3], |mut needed, ((opcode, operand), bits)| {
+                let max_val = match operand.combo {
+                    ComboOperand::Lit(value) => value,
+                    ComboOperand::Reg(register) => (1i64 << bits[register].min(62)) - 1,
+                    ComboOperand::Reserved(_) => 0,
+                };
+
+                let shifted = |needed: &mut [i64; 3], dest: usize| {
+                    let n = std::mem::take(&mut needed[dest]);
+
+                    if n > 0 {
+                        needed[0] = needed[0].max(n + max_val);
+
+                        if let Some(register) = combo_reg(operand) {
+                            needed[register] = needed[register].max(bits[register] as i64);
                         }
-                        .compute();
+                    }
+                };
 
-                        out.first() == Some(&code)
-                    })
-                })
-                .collect_vec()
+                match opcode {
+                    OpCode::Adv => shifted(&mut needed, 0),
+                    OpCode::Bdv => shifted(&mut needed, 1),
+                    OpCode::Cdv => shifted(&mut needed, 2),
+                    OpCode::Bst => {
+                        let n = std::mem::take(&mut needed[1]).min(3);
+
+                        if let Some(register) = combo_reg(operand) {
+                            needed[register] = needed[register].max(n);
+                        }
+                    }
+                    OpCode::Bxc => needed[2] = needed[2].max(needed[1]),
+                    OpCode::Out => {
+                        if let Some(register) = combo_reg(operand) {
+                            needed[register] = needed[register].max(3);
+                        }
+                    }
+                    OpCode::Bxl | OpCode::Jnz | OpCode::Invalid(_) => {}
+                }
+
+                needed
+            });
+
+    match needed[0] {
+        window @ 0..64 => Ok(LoopShape { shift, window }),
+        _ => Err(QuineError::UnboundedWindow),
+    }
+}
+
+/// Finds the lowest A making the program output itself, building A from its highest bits down
+/// one iteration at a time.
+///
+/// Each level only has to check the output of the first iteration, which is looked up by the low
+/// `window` bits of A, as the later iterations see the A of the level above.
+pub fn solution_part_2(computer: &Computer, src_code: &[i32]) -> Result<i64, QuineError> {
+    let shape = analyse_loop(&computer.program)?;
+    let src_code = src_code.iter().map(|&code| code as i64).collect_vec();
+
+    let body = computer.program[..computer.program.len() - 1].to_vec();
+    let mask = (1 << shape.window) - 1;
+    let mut outputs = HashMap::new();
+    let mut output = |a: i64| {
+        *outputs.entry(a & mask).or_insert_with(|| {
+            Computer {
+                registers: [a & mask, computer.registers[1], computer.registers[2]],
+                program: body.clone(),
+            }
+            .compute_with_limits(body.len())
+            .map(|out| out[0])
         })
+    };
+
+    (0..src_code.len())
+        .rev()
+        .try_fold(vec![0], |inits: Vec<i64>, i| {
+            inits
+                .into_iter()
+                // Only the last iteration may leave A at zero
+                .filter(|&init| init != 0 || i == src_code.len() - 1)
+                .flat_map(|init| (0..1 << shape.shift).map(move |x| (init << shape.shift) | x))
+                .map(|x| output(x).map(|out| (x, out)))
+                .filter_ok(|&(_, out)| out == src_code[i])
+                .map_ok(|(x, _)| x)
+                .collect::<Result<Vec<_>, _>>()
+        })?
         .into_iter()
         .min()
-        .unwrap()
+        .ok_or(QuineError::NoSolution)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// Value of a register at the start of the iteration.
+    Reg(usize),
+    Lit(i64),
+    Xor(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Shr(Box<Expr>, Box<Expr>),
+}
+
+impl std::ops::BitXor for Expr {
+    type Output = Expr;
+
+    fn bitxor(self, rhs: Expr) -> Expr {
+        match (self, rhs) {
+            (Expr::Lit(a), Expr::Lit(b)) => Expr::Lit(a ^ b),
+            (x, Expr::Lit(0)) | (Expr::Lit(0), x) => x,
+            (x, y) if x == y => Expr::Lit(0),
+            (Expr::Lit(a), x) => x ^ Expr::Lit(a),
+            (Expr::Xor(x, a), Expr::Lit(b)) if matches!(*a, Expr::Lit(_)) => {
+                *x ^ (*a ^ Expr::Lit(b))
+            }
+            (x, y) => Expr::Xor(Box::new(x), Box::new(y)),
+        }
+    }
+}
+
+impl std::ops::BitAnd for Expr {
+    type Output = Expr;
+
+    fn bitand(self, rhs: Expr) -> Expr {
+        match (self, rhs) {
+            (Expr::Lit(a), Expr::Lit(b)) => Expr::Lit(a & b),
+            (_, Expr::Lit(0)) | (Expr::Lit(0), _) => Expr::Lit(0),
+            (Expr::Lit(a), x) => x & Expr::Lit(a),
+            (Expr::And(x, a), Expr::Lit(b)) if matches!(*a, Expr::Lit(_)) => {
+                *x & (*a & Expr::Lit(b))
+            }
+            (x, y) => Expr::And(Box::new(x), Box::new(y)),
+        }
+    }
+}
+
+impl std::ops::Shr for Expr {
+    type Output = Expr;
+
+    fn shr(self, rhs: Expr) -> Expr {
+        // Shifts outside 0..64 are errors in the VM, so they are never folded away
+        match (self, rhs) {
+            (Expr::Lit(a), Expr::Lit(b @ 0..64)) => Expr::Lit(a >> b),
+            (x, Expr::Lit(0)) => x,
+            (Expr::Shr(x, a), Expr::Lit(b @ 0..64)) if matches!(*a, Expr::Lit(a) if (0..64).contains(&(a + b))) =>
+            {
+                let Expr::Lit(a) = *a else { unreachable!() };
+                *x >> Expr::Lit(a + b)
+            }
+            (x, y) => Expr::Shr(Box::new(x), Box::new(y)),
+        }
+    }
+}
+
+impl Expr {
+    /// Returns the value of the expression, or `None` if it shifts by a value outside 0..64, where
+    /// the VM would fail with [`VmError::ShiftOutOfRange`].
+    pub fn eval(&self, registers: [i64; 3]) -> Option<i64> {
+        match self {
+            Expr::Reg(register) => Some(registers[*register]),
+            Expr::Lit(value) => Some(*value),
+            Expr::Xor(lhs, rhs) => Some(lhs.eval(registers)? ^ rhs.eval(registers)?),
+            Expr::And(lhs, rhs) => Some(lhs.eval(registers)? & rhs.eval(registers)?),
+            Expr::Shr(lhs, rhs) => match rhs.eval(registers)? {
+                shift @ 0..64 => Some(lhs.eval(registers)? >> shift),
+                _ => None,
+            },
+        }
+    }
+
+    /// Returns the expression as Rust code, with the registers named `a`, `b` and `c`.
+    pub fn to_rust(&self) -> String {
+        self.to_string().to_lowercase()
+    }
+
+    fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Reg(_) | Expr::Lit(_) => write!(f, "{self}"),
+            _ => write!(f, "({self})"),
+        }
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Reg(register) => write!(f, "{}", ["A", "B", "C"][*register]),
+            Expr::Lit(value) => write!(f, "{value}"),
+            Expr::Xor(lhs, rhs) => {
+                match lhs.as_ref() {
+                    Expr::Xor(..) => write!(f, "{lhs}")?,
+                    _ => lhs.fmt_operand(f)?,
+                }
+                write!(f, " ^ ")?;
+                match rhs.as_ref() {
+                    Expr::Xor(..) => write!(f, "{rhs}"),
+                    _ => rhs.fmt_operand(f),
+                }
+            }
+            Expr::And(lhs, rhs) => {
+                lhs.fmt_operand(f)?;
+                write!(f, " & ")?;
+                rhs.fmt_operand(f)
+            }
+            Expr::Shr(lhs, rhs) => {
+                lhs.fmt_operand(f)?;
+                write!(f, " >> ")?;
+                rhs.fmt_operand(f)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Iteration {
+    /// Values output during the iteration.
+    pub outputs: Vec<Expr>,
+    /// Register values at the end of the iteration.
+    pub registers: [Expr; 3],
+}
+
+/// Symbolically executes one pass through the program with jumps not taken, expressing every
+/// output and register in terms of the registers at the start.
+pub fn symbolic(program: &[(OpCode, Operand)]) -> Iteration {
+    program.iter().fold(
+        Iteration {
+            outputs: Vec::new(),
+            registers: [Expr::Reg(0), Expr::Reg(1), Expr::Reg(2)],
+        },
+        |mut iteration, (opcode, operand)| {
+            let registers = &mut iteration.registers;
+            let combo = match operand.combo {
+                ComboOperand::Lit(value) | ComboOperand::Reserved(value) => Expr::Lit(value),
+                ComboOperand::Reg(register) => registers[register].clone(),
+            };
+
+            match opcode {
+                OpCode::Adv => registers[0] = registers[0].clone() >> combo,
+                OpCode::Bxl => registers[1] = registers[1].clone() ^ Expr::Lit(operand.lit),
+                OpCode::Bst => registers[1] = combo & Expr::Lit(0b111),
+                OpCode::Bxc => registers[1] = registers[1].clone() ^ registers[2].clone(),
+                OpCode::Out => iteration.outputs.push(combo & Expr::Lit(0b111)),
+                OpCode::Bdv => registers[1] = registers[0].clone() >> combo,
+                OpCode::Cdv => registers[2] = registers[0].clone() >> combo,
+                OpCode::Jnz | OpCode::Invalid(_) => {}
+            }
+
+            iteration
+        },
+    )
+}
+
+/// Returns a Rust closure equivalent to a loop-shaped program, taking A and returning the output.
+pub fn to_rust_closure(program: &[(OpCode, Operand)]) -> Result<String, QuineError> {
+    analyse_loop(program)?;
+
+    let iteration = symbolic(program);
+
+    Ok(format!(
+        "|mut a: i64| {{\n    let mut out = Vec::new();\n    loop {{\n{}        a = {};\n        if a == 0 {{\n            break out;\n        }}\n    }}\n}}",
+        iteration
+            .outputs
+            .iter()
+            .map(|output| format!("        out.push({});\n", output.to_rust()))
+            .join(""),
+        iteration.registers[0].to_rust(),
+    ))
 }
 
 fn main() {
@@ -222,12 +940,18 @@ fn main() {
     let (computer, src_code) = parse_input(&input);
 
     println!("Part 1 solution: {}", solution_part_1(&computer));
-    println!("Part 2 solution: {}", solution_part_2(&computer, &src_code));
-    println!(
-        "Verify part 2: {}",
-        solution_part_1(&Computer {
-            registers: [solution_part_2(&computer, &src_code), 0, 0],
-            program: computer.program,
-        })
-    );
+
+    match solution_part_2(&computer, &src_code) {
+        Ok(a) => {
+            println!("Part 2 solution: {}", a);
+            println!(
+                "Verify part 2: {}",
+                solution_part_1(&Computer {
+                    registers: [a, 0, 0],
+                    program: computer.program,
+                })
+            );
+        }
+        Err(err) => println!("Part 2 unsupported: {}", err),
+    }
 }
@@ -0,0 +1,100 @@
+use aoc_2024::input;
+
+#[path = "17.rs"]
+#[allow(dead_code)]
+mod day;
+
+const USAGE: &str = "\
+Usage:
+  17_asm disasm [file]  disassemble a puzzle input, or the day 17 input if no file is given
+  17_asm asm <file>     assemble a program into its comma-separated form
+  17_asm sym [file]     print each output of one iteration as an expression of the registers
+  17_asm trace <csv|json> [file] [a]
+                        trace every executed instruction, optionally overriding register A";
+
+fn print_trace(computer: &day::Computer, format: &str) {
+    if format == "csv" {
+        println!("{}", day::TraceStep::CSV_HEADER);
+    }
+
+    for step in computer.trace() {
+        match (step, format) {
+            (Ok(step), "csv") => println!("{}", step.to_csv()),
+            (Ok(step), _) => println!("{}", step.to_json()),
+            (Err(err), _) => eprintln!("{err}"),
+        }
+    }
+}
+
+fn print_symbolic(program: &[(day::OpCode, day::Operand)]) {
+    let iteration = day::symbolic(program);
+
+    iteration
+        .outputs
+        .iter()
+        .enumerate()
+        .for_each(|(i, output)| println!("out[{i}] = {output}"));
+
+    ["A", "B", "C"]
+        .iter()
+        .zip(&iteration.registers)
+        .for_each(|(name, value)| println!("{name}' = {value}"));
+
+    match day::to_rust_closure(program) {
+        Ok(closure) => println!("\n{closure}"),
+        Err(err) => eprintln!("\nNo closure: {err}"),
+    }
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["disasm"] => {
+            let (computer, _) = day::parse_input(&input(17));
+            println!("{}", day::disassemble(&computer.program));
+        }
+        ["disasm", path] => {
+            let input = std::fs::read_to_string(path).expect("file should be read");
+            let (computer, _) = day::parse_input(&input);
+            println!("{}", day::disassemble(&computer.program));
+        }
+        ["sym"] => {
+            let (computer, _) = day::parse_input(&input(17));
+            print_symbolic(&computer.program);
+        }
+        ["sym", path] => {
+            let input = std::fs::read_to_string(path).expect("file should be read");
+            let (computer, _) = day::parse_input(&input);
+            print_symbolic(&computer.program);
+        }
+        ["trace", format @ ("csv" | "json")] => {
+            let (computer, _) = day::parse_input(&input(17));
+            print_trace(&computer, format);
+        }
+        ["trace", format @ ("csv" | "json"), path, a @ ..] if a.len() <= 1 => {
+            let input = std::fs::read_to_string(path).expect("file should be read");
+            let (mut computer, _) = day::parse_input(&input);
+
+            if let [a] = a {
+                computer.registers[0] = a.parse().expect("register A should be an integer");
+            }
+
+            print_trace(&computer, format);
+        }
+        ["asm", path] => {
+            let source = std::fs::read_to_string(path).expect("file should be read");
+
+            match day::assemble(&source) {
+                Ok(program) => println!("Program: {program}"),
+                Err(err) => eprintln!("{err}"),
+            }
+        }
+        _ => eprintln!("{USAGE}"),
+    }
+}
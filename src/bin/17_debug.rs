@@ -0,0 +1,189 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    io::{BufRead, Write},
+};
+
+use aoc_2024::input;
+use itertools::Itertools;
+
+#[path = "17.rs"]
+#[allow(dead_code)]
+mod day;
+
+use day::{Computer, VmError};
+
+/// Maximum number of instructions `c` runs before giving up.
+const MAX_STEPS: usize = 10_000_000;
+
+const HELP: &str = "\
+Commands:
+  s [n]            step n instructions (default 1)
+  c                continue until a breakpoint, the program halts, or it loops
+  b <addr>         toggle a breakpoint on an instruction address
+  set <a|b|c> <v>  set a register
+  r                reset to the initial registers
+  h                show this help
+  q                quit";
+
+struct Debugger {
+    computer: Computer,
+    ptr: usize,
+    registers: [i64; 3],
+    out: Vec<i64>,
+    breakpoints: BTreeSet<usize>,
+}
+
+impl Debugger {
+    fn new(computer: Computer) -> Self {
+        Self {
+            ptr: 0,
+            registers: computer.registers,
+            out: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            computer,
+        }
+    }
+
+    fn is_halted(&self) -> bool {
+        self.ptr >= self.computer.program.len()
+    }
+
+    fn reset(&mut self) {
+        self.ptr = 0;
+        self.registers = self.computer.registers;
+        self.out.clear();
+    }
+
+    fn step(&mut self) -> Result<(), VmError> {
+        if self.is_halted() {
+            return Ok(());
+        }
+
+        let (ptr, value) = self.computer.step(self.ptr, &mut self.registers)?;
+        self.ptr = ptr;
+        self.out.extend(value);
+
+        Ok(())
+    }
+
+    fn run(&mut self) -> Result<(), VmError> {
+        let mut states = HashSet::new();
+
+        for _ in 0..MAX_STEPS {
+            if !states.insert((self.ptr, self.registers)) {
+                return Err(VmError::Loop { ptr: self.ptr });
+            }
+
+            self.step()?;
+
+            if self.is_halted() || self.breakpoints.contains(&self.ptr) {
+                return Ok(());
+            }
+        }
+
+        Err(VmError::StepLimit(MAX_STEPS))
+    }
+
+    fn print(&self) {
+        ["A", "B", "C"]
+            .iter()
+            .zip(self.registers)
+            .for_each(|(name, value)| println!("{name}: {value} | 0o{value:o} | 0b{value:b}"));
+
+        println!();
+
+        self.computer
+            .program
+            .iter()
+            .enumerate()
+            .for_each(|(ptr, (opcode, operand))| {
+                println!(
+                    "{}{} {:>2}: {:<6} ; {}",
+                    if ptr == self.ptr { ">" } else { " " },
+                    if self.breakpoints.contains(&ptr) {
+                        "*"
+                    } else {
+                        " "
+                    },
+                    ptr * 2,
+                    opcode.disassemble(operand),
+                    opcode.annotate(operand),
+                )
+            });
+
+        if self.is_halted() {
+            println!(">  {:>2}: (halted)", self.ptr * 2);
+        }
+
+        println!();
+        println!("Output: {}", self.out.iter().join(","));
+    }
+
+    fn exec(&mut self, command: &str) -> Result<(), String> {
+        let args = command.split_whitespace().collect_vec();
+
+        match args.as_slice() {
+            ["s"] => self.step().map_err(|e| e.to_string())?,
+            ["s", n] => (0..n.parse::<usize>().map_err(|e| e.to_string())?)
+                .try_for_each(|_| self.step())
+                .map_err(|e| e.to_string())?,
+            ["c"] => self.run().map_err(|e| e.to_string())?,
+            ["b", addr] => {
+                let ptr = match addr.parse::<usize>().map_err(|e| e.to_string())? {
+                    addr if addr % 2 == 0 => addr / 2,
+                    addr => return Err(format!("Address should be even: {addr}")),
+                };
+
+                if !self.breakpoints.remove(&ptr) {
+                    self.breakpoints.insert(ptr);
+                }
+            }
+            ["set", register, value] => {
+                let register = match *register {
+                    "a" | "A" => 0,
+                    "b" | "B" => 1,
+                    "c" | "C" => 2,
+                    _ => return Err(format!("Invalid register: {register}")),
+                };
+
+                self.registers[register] = value.parse::<i64>().map_err(|e| e.to_string())?;
+            }
+            ["r"] => self.reset(),
+            ["h"] => println!("{HELP}"),
+            _ => return Err(format!("Unknown command: {command}")),
+        }
+
+        Ok(())
+    }
+}
+
+fn main() {
+    let input = input(17);
+
+    let (computer, _) = day::parse_input(&input);
+    let mut debugger = Debugger::new(computer);
+
+    println!("{HELP}\n");
+    debugger.print();
+
+    let mut lines = std::io::stdin().lock().lines();
+
+    loop {
+        print!("\n(debug) ");
+        std::io::stdout().flush().expect("stdout should be flushed");
+
+        let line = match lines.next() {
+            Some(line) => line.expect("line should be read"),
+            None => break,
+        };
+
+        match line.trim() {
+            "q" => break,
+            "" => continue,
+            command => match debugger.exec(command) {
+                Ok(()) => debugger.print(),
+                Err(err) => println!("{err}"),
+            },
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::{BinaryHeap, HashMap, HashSet};
 
-use aoc_2024::input;
+use aoc_2024::{input, Connectivity, DistanceField, Grid};
 use glam::*;
 use itertools::{FoldWhile, Itertools};
 use nom::{
@@ -8,27 +8,137 @@ use nom::{
     sequence::separated_pair, IResult,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub size: IVec2,
+    /// Number of fallen bytes for part 1.
+    pub time: usize,
+    pub start: IVec2,
+    pub end: IVec2,
+    /// Whether diagonal moves are allowed.
+    pub is_diagonal: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::with_size(IVec2::splat(71), 1024)
+    }
+}
+
+impl Config {
+    /// Returns the config going from the top left to the bottom right corner.
+    pub fn with_size(size: IVec2, time: usize) -> Self {
+        Self {
+            size,
+            time,
+            start: IVec2::ZERO,
+            end: size - IVec2::ONE,
+            is_diagonal: false,
+        }
+    }
+
+    /// Infers the square grid size from the furthest byte position, as the puzzle grid is always
+    /// square even when no byte lands on its last row or column.
+    ///
+    /// The time cannot be inferred, so it is a heuristic: 12 for the 7x7 example and 1024, as in
+    /// the real puzzle, for any other size. Pass `--time` for anything else.
+    pub fn infer(positions: &[IVec2]) -> Self {
+        let size = IVec2::splat(
+            positions
+                .iter()
+                .map(|pos| pos.max_element() + 1)
+                .max()
+                .unwrap_or(1),
+        );
+
+        Self::with_size(
+            size,
+            match size {
+                IVec2 { x: 7, y: 7 } => 12,
+                _ => 1024,
+            },
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct Space {
     pub positions: Vec<IVec2>,
     pub size: IVec2,
+    pub start: IVec2,
+    pub end: IVec2,
+    pub is_diagonal: bool,
+    /// Index of the first byte to fall on each cell.
+    pub fall_times: Grid<Option<usize>>,
 }
 
 impl Space {
+    pub fn new(positions: Vec<IVec2>, config: &Config) -> Self {
+        let fall_times = positions.iter().enumerate().rev().fold(
+            Grid::new(config.size, None),
+            |mut fall_times, (time, &pos)| {
+                if let Some(cell) = fall_times.get_mut(pos) {
+                    *cell = Some(time);
+                }
+                fall_times
+            },
+        );
+
+        [("start", config.start), ("exit", config.end)]
+            .iter()
+            .for_each(|&(name, pos)| {
+                assert!(
+                    fall_times.contains(pos),
+                    "{name} {},{} should be within the {}x{} grid",
+                    pos.x,
+                    pos.y,
+                    config.size.x,
+                    config.size.y,
+                )
+            });
+
+        Self {
+            positions,
+            size: config.size,
+            start: config.start,
+            end: config.end,
+            is_diagonal: config.is_diagonal,
+            fall_times,
+        }
+    }
+
+    pub fn connectivity(&self) -> Connectivity {
+        match self.is_diagonal {
+            true => Connectivity::Eight,
+            false => Connectivity::Four,
+        }
+    }
+
+    /// Returns the directions that can be moved in.
+    pub fn dirs(&self) -> &'static [IVec2] {
+        self.connectivity().dirs()
+    }
+
     pub fn at(&self, pos: IVec2) -> bool {
-        pos.x < 0
-            || pos.y < 0
-            || pos.x >= self.size.x
-            || pos.y >= self.size.y
-            || self.positions.contains(&pos)
+        self.fall_times.get(pos).is_none_or(Option::is_some)
     }
 
     pub fn at_with_time(&self, pos: IVec2, time: usize) -> bool {
-        pos.x < 0
-            || pos.y < 0
-            || pos.x >= self.size.x
-            || pos.y >= self.size.y
-            || self.positions[..time].contains(&pos)
+        self.fall_times
+            .get(pos)
+            .is_none_or(|fall_time| fall_time.is_some_and(|t| t < time))
+    }
+
+    /// Returns the index of the first byte to fall on `pos`, if any.
+    pub fn fall_time(&self, pos: IVec2) -> Option<usize> {
+        self.fall_times.get(pos).copied().flatten()
+    }
+
+    /// Returns the cells still free once `time` bytes have fallen.
+    pub fn free_at(&self, time: usize) -> impl Iterator<Item = IVec2> + '_ {
+        self.fall_times
+            .positions()
+            .filter(move |&pos| !self.at_with_time(pos, time))
     }
 }
 
@@ -98,12 +208,28 @@ pub fn parse_input(input: &str) -> Vec<IVec2> {
         .collect()
 }
 
-pub fn solution_part_1(space: &Space, time: usize) -> i32 {
-    std::iter::repeat(())
+pub fn solution_part_1(space: &Space, time: usize) -> Option<i32> {
+    DistanceField::new(
+        space.size,
+        |pos| !space.at_with_time(pos, time),
+        [space.start],
+        space.connectivity(),
+    )
+    .cost_at(space.end)
+}
+
+/// Returns a shortest path from the start to the exit, from the exit backwards, once `time` bytes
+/// have fallen.
+pub fn shortest_path(space: &Space, time: usize) -> Option<Vec<IVec2>> {
+    if space.at_with_time(space.start, time) || space.at_with_time(space.end, time) {
+        return None;
+    }
+
+    let (_, visited, end) = std::iter::repeat(())
         .fold_while(
             (
-                BinaryHeap::from([Instance::new(IVec2::ZERO)]),
-                HashSet::new(),
+                BinaryHeap::from([Instance::new(space.start)]),
+                HashMap::<IVec2, Option<IVec2>>::new(),
                 None,
             ),
             |(mut queue, mut visited, end), _| {
@@ -112,107 +238,293 @@ pub fn solution_part_1(space: &Space, time: usize) -> i32 {
                     None => return FoldWhile::Done((queue, visited, end)),
                 };
 
-                if visited.contains(&curr.pos) {
+                if visited.contains_key(&curr.pos) {
                     return FoldWhile::Continue((queue, visited, end));
                 }
 
-                visited.insert(curr.pos);
+                visited.insert(curr.pos, curr.prev);
 
-                if curr.pos == space.size - IVec2::ONE {
-                    return FoldWhile::Done((queue, visited, Some(curr.dist)));
+                if curr.pos == space.end {
+                    return FoldWhile::Done((queue, visited, Some(curr.pos)));
                 }
 
-                [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
+                space
+                    .dirs()
                     .iter()
                     .map(|&dir| curr.pos + dir)
-                    .filter(|&next| !space.at_with_time(next, time) && !visited.contains(&next))
-                    .for_each(|next| queue.push(Instance::with_distance(next, curr.dist + 1)));
+                    .filter(|&next| !space.at_with_time(next, time) && !visited.contains_key(&next))
+                    .for_each(|next| {
+                        queue.push(Instance::with_prev(next, curr.dist + 1, curr.pos))
+                    });
 
                 FoldWhile::Continue((queue, visited, end))
             },
         )
-        .into_inner()
-        .2
-        .unwrap()
+        .into_inner();
+
+    end.map(|end| {
+        std::iter::successors(Some(end), |&pos| visited.get(&pos).copied()?).collect_vec()
+    })
+}
+
+/// A shortest path found once `time` bytes have fallen.
+#[derive(Debug, Clone)]
+pub struct PathFrame {
+    pub time: usize,
+    pub path: Vec<IVec2>,
 }
 
-pub fn solution_part_2(space: &Space) -> IVec2 {
-    let init_path = (0..space.size.x)
-        .map(|x| IVec2::new(x, 0))
-        .chain((0..space.size.y).map(|y| IVec2::new(space.size.x - 1, y)))
-        .collect_vec();
+/// Returns the shortest path each time the previous one is invalidated by a falling byte, and the
+/// time at which the exit becomes unreachable, if it does. Returns no frames if the start or exit
+/// is off the grid.
+pub fn path_frames(space: &Space) -> (Vec<PathFrame>, Option<usize>) {
+    let init_path = match shortest_path(space, 0) {
+        Some(path) => path,
+        None => return (Vec::new(), None),
+    };
 
     (0..space.positions.len())
-        .fold_while((init_path, None), |(prev_path, blockage), time| {
-            let new_pos = space.positions[time];
+        .fold_while(
+            (
+                vec![PathFrame {
+                    time: 0,
+                    path: init_path,
+                }],
+                None,
+            ),
+            |(mut frames, blockage), time| {
+                let new_pos = space.positions[time];
+                let prev_path = &frames.last().expect("frames should not be empty").path;
 
-            if !prev_path.contains(&new_pos) {
-                return FoldWhile::Continue((prev_path, blockage));
-            }
+                if !prev_path.contains(&new_pos) {
+                    return FoldWhile::Continue((frames, blockage));
+                }
 
-            let (_, visited, end) = std::iter::repeat(())
-                .fold_while(
-                    (
-                        BinaryHeap::from([Instance::new(IVec2::ZERO)]),
-                        HashMap::<IVec2, Option<IVec2>>::new(),
-                        None,
-                    ),
-                    |(mut queue, mut visited, end), _| {
-                        let curr = match queue.pop() {
-                            Some(curr) => curr,
-                            None => return FoldWhile::Done((queue, visited, end)),
-                        };
-
-                        if visited.contains_key(&curr.pos) {
-                            return FoldWhile::Continue((queue, visited, end));
-                        }
-
-                        visited.insert(curr.pos, curr.prev);
-
-                        if curr.pos == space.size - IVec2::ONE {
-                            return FoldWhile::Done((queue, visited, Some(curr.pos)));
-                        }
-
-                        [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
-                            .iter()
-                            .map(|&dir| curr.pos + dir)
-                            .filter(|&next| {
-                                !space.at_with_time(next, time + 1) && !visited.contains_key(&next)
-                            })
-                            .for_each(|next| {
-                                queue.push(Instance::with_prev(next, curr.dist + 1, curr.pos))
-                            });
-
-                        FoldWhile::Continue((queue, visited, end))
-                    },
-                )
-                .into_inner();
-
-            match end {
-                Some(end) => {
-                    let path =
-                        std::iter::successors(Some(end), |&pos| visited.get(&pos).copied()?)
-                            .collect_vec();
-                    FoldWhile::Continue((path, blockage))
+                match shortest_path(space, time + 1) {
+                    Some(path) => {
+                        frames.push(PathFrame {
+                            time: time + 1,
+                            path,
+                        });
+                        FoldWhile::Continue((frames, blockage))
+                    }
+                    None => FoldWhile::Done((frames, Some(time))),
                 }
-                None => FoldWhile::Done((prev_path, Some(new_pos))),
-            }
-        })
+            },
+        )
+        .into_inner()
+}
+
+/// Returns the fallen bytes connected to the byte falling at `time`, which together cut the start
+/// off from the exit. Bytes connect diagonally unless diagonal moves are allowed.
+pub fn cut(space: &Space, time: usize) -> HashSet<IVec2> {
+    std::iter::repeat(())
+        .fold_while(
+            (vec![space.positions[time]], HashSet::new()),
+            |(mut stack, mut cut), _| {
+                let curr = match stack.pop() {
+                    Some(curr) => curr,
+                    None => return FoldWhile::Done((stack, cut)),
+                };
+
+                if !cut.insert(curr) {
+                    return FoldWhile::Continue((stack, cut));
+                }
+
+                match space.is_diagonal {
+                    true => Connectivity::Four,
+                    false => Connectivity::Eight,
+                }
+                .dirs()
+                .iter()
+                .map(|&dir| curr + dir)
+                .filter(|&next| {
+                    space.fall_time(next).is_some_and(|t| t <= time) && !cut.contains(&next)
+                })
+                .for_each(|next| stack.push(next));
+
+                FoldWhile::Continue((stack, cut))
+            },
+        )
         .into_inner()
         .1
-        .unwrap()
+}
+
+/// Disjoint-set forest over cell indices, with path compression and union by size.
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    pub parent: Vec<usize>,
+    pub size: Vec<usize>,
+}
+
+impl DisjointSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    pub fn find(&mut self, i: usize) -> usize {
+        let mut root = i;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        let mut curr = i;
+        while curr != root {
+            curr = std::mem::replace(&mut self.parent[curr], root);
+        }
+
+        root
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+
+        if a == b {
+            return;
+        }
+
+        let (small, large) = match self.size[a] < self.size[b] {
+            true => (a, b),
+            false => (b, a),
+        };
+
+        self.parent[small] = large;
+        self.size[large] += self.size[small];
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// Re-runs the search whenever a byte lands on the current shortest path.
+    Incremental,
+    /// Adds every byte, then removes them in reverse, merging free cells until the start and the
+    /// exit are connected.
+    UnionFind,
+    /// Binary searches the time at which the exit becomes unreachable.
+    BinarySearch,
+}
+
+impl Engine {
+    /// Returns the index of the byte that cuts the start off from the exit, if any.
+    pub fn blockage(&self, space: &Space) -> Option<usize> {
+        match self {
+            Engine::Incremental => path_frames(space).1,
+            Engine::UnionFind => Self::union_find(space),
+            Engine::BinarySearch => {
+                shortest_path(space, 0)?;
+
+                let times = (0..space.positions.len()).collect_vec();
+
+                match times.partition_point(|&time| shortest_path(space, time + 1).is_some()) {
+                    time if time < times.len() => Some(time),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn union_find(space: &Space) -> Option<usize> {
+        let grid = &space.fall_times;
+        if !grid.contains(space.start) || !grid.contains(space.end) {
+            return None;
+        }
+
+        let (start, end) = (grid.index(space.start), grid.index(space.end));
+
+        let mut set = DisjointSet::new(grid.cells.len());
+
+        let free = |set: &mut DisjointSet, pos: IVec2, time: usize| {
+            space
+                .dirs()
+                .iter()
+                .map(|&dir| pos + dir)
+                .filter(|&next| !space.at_with_time(next, time))
+                .for_each(|next| set.union(grid.index(pos), grid.index(next)));
+        };
+
+        space
+            .free_at(space.positions.len())
+            .for_each(|pos| free(&mut set, pos, space.positions.len()));
+
+        if set.find(start) == set.find(end) {
+            return None;
+        }
+
+        (0..space.positions.len()).rev().find(|&time| {
+            let pos = space.positions[time];
+
+            if space.fall_time(pos) == Some(time) {
+                free(&mut set, pos, time);
+            }
+
+            set.find(start) == set.find(end)
+        })
+    }
+}
+
+pub fn solution_part_2(space: &Space, engine: Engine) -> Option<IVec2> {
+    engine.blockage(space).map(|time| space.positions[time])
+}
+
+/// Parses `--file <path>`, `--size <n>`, `--time <t>`, `--start <x,y>`, `--end <x,y>` and
+/// `--diagonal`, inferring anything not given from the input. Shared with the animation.
+pub fn parse_args(args: &[String]) -> (String, Config) {
+    let value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .map(|i| args.get(i + 1).expect("flag should have a value").as_str())
+    };
+    let pos = |value: &str| parse_input(value)[0];
+
+    let input = match value("--file") {
+        Some(path) => std::fs::read_to_string(path).expect("file should be read"),
+        None => input(18),
+    };
+
+    let inferred = Config::infer(&parse_input(&input));
+    let size = value("--size").map_or(inferred.size, |size| {
+        IVec2::splat(size.parse().expect("size should be an integer"))
+    });
+    let config = Config::with_size(
+        size,
+        value("--time").map_or(inferred.time, |time| {
+            time.parse().expect("time should be an integer")
+        }),
+    );
+
+    (
+        input,
+        Config {
+            start: value("--start").map_or(config.start, pos),
+            end: value("--end").map_or(config.end, pos),
+            is_diagonal: args.iter().any(|arg| arg == "--diagonal"),
+            ..config
+        },
+    )
 }
 
 fn main() {
-    let (input, size, time) = (input(18), IVec2::splat(71), 1024);
+    let (input, config) = parse_args(&std::env::args().skip(1).collect_vec());
 
     let positions = parse_input(&input);
 
-    let space = Space { positions, size };
+    let space = Space::new(positions, &config);
 
-    println!("Part 1 solution: {}", solution_part_1(&space, time));
-    println!("Part 2 solution: {}", {
-        let solution = solution_part_2(&space);
-        format!("{},{}", solution.x, solution.y)
-    });
+    println!(
+        "Part 1 solution: {}",
+        match solution_part_1(&space, config.time) {
+            Some(steps) => steps.to_string(),
+            None => "exit is unreachable".to_string(),
+        }
+    );
+    println!(
+        "Part 2 solution: {}",
+        match solution_part_2(&space, Engine::UnionFind) {
+            Some(solution) => format!("{},{}", solution.x, solution.y),
+            None => "exit is never blocked".to_string(),
+        }
+    );
 }
@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+
+use crossterm::{
+    event::{self, Event, KeyCode, MouseEventKind},
+    terminal::{disable_raw_mode, enable_raw_mode},
+    ExecutableCommand,
+};
+use glam::*;
+use itertools::Itertools;
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    style::Color,
+    symbols::Marker,
+    widgets::{
+        canvas::{Canvas, Points},
+        Block,
+    },
+};
+
+#[path = "18.rs"]
+#[allow(dead_code)]
+mod day;
+
+use day::{PathFrame, Space};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Tile {
+    Byte,
+    Fallen,
+    Path,
+    Cut,
+    Endpoint,
+}
+
+impl Tile {
+    fn color(self) -> Color {
+        match self {
+            Tile::Byte => Color::DarkGray,
+            Tile::Fallen => Color::Yellow,
+            Tile::Path => Color::Green,
+            Tile::Cut => Color::Red,
+            Tile::Endpoint => Color::Cyan,
+        }
+    }
+}
+
+struct Fall {
+    space: Space,
+    frames: Vec<PathFrame>,
+    blockage: Option<usize>,
+    cut: HashSet<IVec2>,
+}
+
+impl Fall {
+    fn new(space: Space) -> Self {
+        let (frames, blockage) = day::path_frames(&space);
+
+        Self {
+            cut: blockage.map_or_else(HashSet::new, |blockage| day::cut(&space, blockage)),
+            frames,
+            blockage,
+            space,
+        }
+    }
+
+    /// Returns the number of bytes fallen when the animation ends.
+    fn end_time(&self) -> usize {
+        self.blockage
+            .map_or(self.space.positions.len(), |blockage| blockage + 1)
+    }
+
+    /// Returns whether the exit is blocked with `time` bytes fallen.
+    fn is_blocked(&self, time: usize) -> bool {
+        self.blockage.is_some_and(|blockage| time > blockage)
+    }
+
+    fn path(&self, time: usize) -> &[IVec2] {
+        let frame = self
+            .frames
+            .iter()
+            .rev()
+            .find(|frame| frame.time <= time)
+            .expect("first frame should start at time 0");
+
+        &frame.path
+    }
+
+    /// Returns the tiles with `time` bytes fallen.
+    fn tiles(&self, time: usize) -> impl Iterator<Item = (IVec2, Tile)> + '_ {
+        let is_cut = self.is_blocked(time);
+
+        let bytes =
+            self.space.positions[..time]
+                .iter()
+                .enumerate()
+                .map(move |(i, &pos)| match pos {
+                    _ if is_cut && self.cut.contains(&pos) => (pos, Tile::Cut),
+                    _ if i + 1 == time => (pos, Tile::Fallen),
+                    _ => (pos, Tile::Byte),
+                });
+
+        let path = match is_cut {
+            true => [].as_slice(),
+            false => self.path(time),
+        };
+
+        bytes
+            .chain(path.iter().map(|&pos| (pos, Tile::Path)))
+            .chain([self.space.start, self.space.end].map(|pos| (pos, Tile::Endpoint)))
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    log::info!("Computing day 18 paths");
+
+    let (input, config) = day::parse_args(&std::env::args().skip(1).collect_vec());
+    let positions = day::parse_input(&input);
+    let size = config.size;
+    let fall = Fall::new(Space::new(positions, &config));
+
+    log::info!("Starting terminal");
+
+    std::io::stdout()
+        .execute(crossterm::event::EnableMouseCapture)
+        .expect("mouse should be captured");
+    enable_raw_mode().expect("raw mode should be enabled");
+
+    let mut terminal = ratatui::init();
+    terminal.clear().expect("terminal should be cleared");
+
+    let term_size = (size / ivec2(1, 2) + ivec2(2, 2)).as_u16vec2();
+
+    let mut speed: i32 = 100; // bytes per second
+    let mut elapsed = 0.0;
+    let mut last_frame = std::time::Instant::now();
+    let mut is_running = true;
+
+    while is_running {
+        elapsed += last_frame.elapsed().as_secs_f32() * speed as f32;
+        last_frame = std::time::Instant::now();
+
+        let time = (elapsed as usize).min(fall.end_time());
+        let points = fall.tiles(time).into_group_map_by(|&(_, tile)| tile);
+
+        terminal
+            .draw(|frame| {
+                let [center] = Layout::horizontal([Constraint::Length(term_size.x)])
+                    .flex(Flex::Center)
+                    .areas(frame.area());
+                let [center] = Layout::vertical([Constraint::Length(term_size.y)])
+                    .flex(Flex::Center)
+                    .areas(center);
+
+                let canvas = Canvas::default()
+                    .block(
+                        Block::bordered()
+                            .title_top("Advent of Code 2024 - Day 18 RAM Run")
+                            .title_bottom(format!(
+                                "Bytes: {} / {} | Path: {} | Speed: {}/s | Space to restart, Q to quit",
+                                time,
+                                fall.end_time(),
+                                match fall.is_blocked(time) {
+                                    true => "Blocked".to_string(),
+                                    false => (fall.path(time).len() - 1).to_string(),
+                                },
+                                speed,
+                            )),
+                    )
+                    .marker(Marker::HalfBlock)
+                    .x_bounds([0.0, size.x as f64])
+                    .y_bounds([0.0, size.y as f64])
+                    .paint(|ctx| {
+                        [Tile::Byte, Tile::Path, Tile::Fallen, Tile::Cut, Tile::Endpoint]
+                            .iter()
+                            .filter_map(|tile| points.get_key_value(tile))
+                            .for_each(|(tile, points)| {
+                                ctx.draw(&Points {
+                                    coords: &points
+                                        .iter()
+                                        .map(|(pos, _)| (pos.x as f64, (size.y - pos.y) as f64))
+                                        .collect_vec(),
+                                    color: tile.color(),
+                                });
+                            });
+                    });
+
+                frame.render_widget(canvas, center);
+            })
+            .expect("frame should be rendered");
+
+        while event::poll(std::time::Duration::from_millis(10)).expect("event should be polled") {
+            match event::read().expect("event should be read") {
+                Event::Key(event) => match event.code {
+                    KeyCode::Char(' ') => {
+                        elapsed = 0.0;
+                    }
+                    KeyCode::Char('q') => {
+                        is_running = false;
+                        break;
+                    }
+                    _ => {}
+                },
+                Event::Mouse(event) => match event.kind {
+                    MouseEventKind::ScrollUp => {
+                        let speed_delta = match speed {
+                            0..10 => 1,
+                            10..100 => 10,
+                            100..1000 => 100,
+                            _ => 0,
+                        };
+                        speed = (speed + speed_delta).min(1000);
+                    }
+                    MouseEventKind::ScrollDown => {
+                        let speed_delta = match speed {
+                            0..=10 => 1,
+                            11..=100 => 10,
+                            101..=1000 => 100,
+                            _ => 0,
+                        };
+                        speed = (speed - speed_delta).max(0);
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+
+    ratatui::restore();
+
+    disable_raw_mode().expect("raw mode should be disabled");
+    std::io::stdout()
+        .execute(crossterm::event::DisableMouseCapture)
+        .expect("mouse should be released");
+}
@@ -1,3 +1,5 @@
+use std::collections::{BTreeSet, HashMap};
+
 use aoc_2024::input;
 use glam::*;
 use itertools::Itertools;
@@ -11,7 +13,7 @@ use nom::{
 
 pub fn parse_input(input: &str) -> (Vec<&str>, Vec<&str>) {
     fn pattern(input: &str) -> IResult<&str, &str> {
-        take_while1(|c| c == 'w' || c == 'u' || c == 'b' || c == 'r' || c == 'g')(input)
+        take_while1(|c: char| !c.is_whitespace() && c != ',')(input)
     }
 
     let (_, (patterns, designs)) = (separated_pair(
@@ -24,55 +26,214 @@ pub fn parse_input(input: &str) -> (Vec<&str>, Vec<&str>) {
     (patterns, designs)
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct TrieNode {
+    pub children: HashMap<u8, usize>,
+    pub is_pattern: bool,
+}
+
+/// Trie of towel patterns, reusable across designs. Duplicate patterns are merged.
+#[derive(Debug, Clone)]
+pub struct TowelSet {
+    pub nodes: Vec<TrieNode>,
+}
+
+impl TowelSet {
+    pub fn new(patterns: &[&str]) -> Self {
+        let nodes = patterns
+            .iter()
+            .fold(vec![TrieNode::default()], |mut nodes, pattern| {
+                let node = pattern
+                    .bytes()
+                    .fold(0, |node, c| match nodes[node].children.get(&c) {
+                        Some(&child) => child,
+                        None => {
+                            nodes.push(TrieNode::default());
+                            let child = nodes.len() - 1;
+                            nodes[node].children.insert(c, child);
+                            child
+                        }
+                    });
+                nodes[node].is_pattern = true;
+                nodes
+            });
+
+        Self { nodes }
+    }
+
+    /// Returns the end index of every pattern matching `design` from `start`.
+    pub fn matches<'a>(
+        &'a self,
+        design: &'a [u8],
+        start: usize,
+    ) -> impl Iterator<Item = usize> + 'a {
+        design[start..]
+            .iter()
+            .scan(0, |node, c| {
+                *node = *self.nodes[*node].children.get(c)?;
+                Some(*node)
+            })
+            .enumerate()
+            .filter(|&(_, node)| self.nodes[node].is_pattern)
+            .map(move |(i, _)| start + i + 1)
+    }
+
+    pub fn is_possible(&self, design: &str) -> bool {
+        let design = design.as_bytes();
+
+        (0..design.len())
+            .fold(
+                (0..=design.len()).map(|i| i == 0).collect_vec(),
+                |mut possible, i| {
+                    if possible[i] {
+                        self.matches(design, i).for_each(|end| possible[end] = true);
+                    }
+                    possible
+                },
+            )
+            .last()
+            .copied()
+            .unwrap()
+    }
+
+    pub fn count(&self, design: &str) -> i64 {
+        let design = design.as_bytes();
+
+        (0..design.len())
+            .fold(
+                (0..=design.len())
+                    .map(|i| if i == 0 { 1 } else { 0 })
+                    .collect_vec(),
+                |mut counts, i| {
+                    if counts[i] > 0 {
+                        self.matches(design, i)
+                            .for_each(|end| counts[end] += counts[i]);
+                    }
+                    counts
+                },
+            )
+            .last()
+            .copied()
+            .unwrap()
+    }
+
+    /// Returns the stripe colours used by the patterns.
+    pub fn alphabet(&self) -> BTreeSet<char> {
+        self.nodes
+            .iter()
+            .flat_map(|node| node.children.keys())
+            .map(|&c| c as char)
+            .collect()
+    }
+
+    /// Returns whether the rest of `design` from each index can be made.
+    fn possible_from(&self, design: &[u8]) -> Vec<bool> {
+        (0..design.len()).rev().fold(
+            (0..=design.len()).map(|i| i == design.len()).collect_vec(),
+            |mut possible, i| {
+                possible[i] = self.matches(design, i).any(|end| possible[end]);
+                possible
+            },
+        )
+    }
+
+    /// Lazily enumerates every arrangement of patterns making `design`, shortest patterns first.
+    pub fn arrangements<'a>(&'a self, design: &'a str) -> impl Iterator<Item = Vec<&'a str>> + 'a {
+        let bytes = design.as_bytes();
+        let possible = self.possible_from(bytes);
+        let ends = move |start: usize| {
+            self.matches(bytes, start)
+                .filter(|&end| possible[end])
+                .collect_vec()
+                .into_iter()
+                .rev()
+                .collect_vec()
+        };
+
+        let mut stack = vec![ends(0)];
+        let mut bounds = vec![0];
+
+        std::iter::from_fn(move || loop {
+            match stack.last_mut()?.pop() {
+                None => {
+                    stack.pop();
+                    bounds.pop();
+                }
+                Some(end) if end == bytes.len() => {
+                    return Some(
+                        bounds
+                            .iter()
+                            .chain([&end])
+                            .tuple_windows()
+                            .map(|(&a, &b)| &design[a..b])
+                            .collect(),
+                    );
+                }
+                Some(end) => {
+                    bounds.push(end);
+                    stack.push(ends(end));
+                }
+            }
+        })
+    }
+
+    /// Returns the arrangement whose number of patterns is preferred by `is_better`.
+    fn extreme_arrangement<'a>(
+        &self,
+        design: &'a str,
+        is_better: impl Fn(usize, usize) -> bool,
+    ) -> Option<Vec<&'a str>> {
+        let bytes = design.as_bytes();
+        let len = bytes.len();
+
+        let best = (0..len).rev().fold(
+            (0..=len)
+                .map(|i| (i == len).then_some((0, len)))
+                .collect_vec(),
+            |mut best, i| {
+                best[i] = self
+                    .matches(bytes, i)
+                    .filter_map(|end| best[end].map(|(count, _)| (count + 1, end)))
+                    .reduce(|a, b| match is_better(b.0, a.0) {
+                        true => b,
+                        false => a,
+                    });
+                best
+            },
+        );
+
+        best[0]?;
+
+        Some(
+            std::iter::successors(Some(0), |&i| (i < len).then(|| best[i].unwrap().1))
+                .tuple_windows()
+                .map(|(a, b)| &design[a..b])
+                .collect(),
+        )
+    }
+
+    pub fn fewest_towels<'a>(&self, design: &'a str) -> Option<Vec<&'a str>> {
+        self.extreme_arrangement(design, |a, b| a < b)
+    }
+
+    pub fn most_towels<'a>(&self, design: &'a str) -> Option<Vec<&'a str>> {
+        self.extreme_arrangement(design, |a, b| a > b)
+    }
+}
+
 pub fn solution_part_1(patterns: &[&str], designs: &[&str]) -> i32 {
+    let towels = TowelSet::new(patterns);
+
     designs
         .iter()
-        .filter(|&design| {
-            let completes = (0..=design.len()).map(|i| i == 0).collect_vec();
-
-            (1..=design.len())
-                .map(|len| (len, &design.as_bytes()[..len]))
-                .fold(completes, |mut completes, (i, subdesign)| {
-                    completes[i] = patterns.iter().any(|&pattern| {
-                        subdesign.ends_with(pattern.as_bytes())
-                            && subdesign.len() >= pattern.len()
-                            && completes[subdesign.len() - pattern.len()]
-                    });
-                    completes
-                })
-                .last()
-                .copied()
-                .unwrap()
-        })
+        .filter(|&design| towels.is_possible(design))
         .count() as i32
 }
 
 pub fn solution_part_2(patterns: &[&str], designs: &[&str]) -> i64 {
-    designs
-        .iter()
-        .map(|&design| {
-            let counts = (0..=design.len())
-                .map(|i| if i == 0 { 1 } else { 0 })
-                .collect_vec();
-
-            (1..=design.len())
-                .map(|len| (len, &design.as_bytes()[..len]))
-                .fold(counts, |mut counts, (i, subdesign)| {
-                    counts[i] = patterns
-                        .iter()
-                        .flat_map(|&pattern| {
-                            (subdesign.ends_with(pattern.as_bytes())
-                                && subdesign.len() >= pattern.len())
-                            .then(|| counts[subdesign.len() - pattern.len()])
-                        })
-                        .sum();
-                    counts
-                })
-                .last()
-                .copied()
-                .unwrap()
-        })
-        .sum::<i64>()
+    let towels = TowelSet::new(patterns);
+
+    designs.iter().map(|&design| towels.count(design)).sum()
 }
 
 fn main() {
@@ -80,6 +241,32 @@ fn main() {
 
     let (patterns, designs) = parse_input(&input);
 
+    if let Some("arrangements") = std::env::args().nth(1).as_deref() {
+        let limit = std::env::args().nth(2).map_or(5, |limit| {
+            limit.parse().expect("limit should be an integer")
+        });
+        let towels = TowelSet::new(&patterns);
+
+        println!("Alphabet: {}", towels.alphabet().iter().join(""));
+
+        designs.iter().for_each(|&design| {
+            println!("\n{design}: {} arrangements", towels.count(design));
+
+            if let (Some(fewest), Some(most)) =
+                (towels.fewest_towels(design), towels.most_towels(design))
+            {
+                println!("  Fewest: {}", fewest.join(" "));
+                println!("  Most:   {}", most.join(" "));
+            }
+
+            towels
+                .arrangements(design)
+                .take(limit)
+                .for_each(|arrangement| println!("  {}", arrangement.join(" ")));
+        });
+        return;
+    }
+
     println!("Part 1 solution: {}", solution_part_1(&patterns, &designs));
     println!("Part 2 solution: {}", solution_part_2(&patterns, &designs));
 }
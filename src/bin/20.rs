@@ -1,6 +1,6 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashSet, VecDeque};
 
-use aoc_2024::input;
+use aoc_2024::{input, Connectivity, DistanceField};
 use glam::*;
 use itertools::{FoldWhile, Itertools};
 
@@ -14,121 +14,6 @@ impl MapAt for &[&[u8]] {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PathMapNode {
-    Wall,
-    Path(PathMapPathNode),
-}
-
-impl PathMapNode {
-    pub fn path(&self) -> Option<PathMapPathNode> {
-        match self {
-            Self::Wall => None,
-            Self::Path(path) => Some(*path),
-        }
-    }
-
-    pub fn path_mut(&mut self) -> Option<&mut PathMapPathNode> {
-        match self {
-            Self::Wall => None,
-            Self::Path(path) => Some(path),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PathMapPathNode {
-    Cost(i32),
-    NotVisited,
-}
-
-impl PathMapPathNode {
-    pub fn cost(&self) -> Option<i32> {
-        match self {
-            Self::Cost(cost) => Some(*cost),
-            Self::NotVisited => None,
-        }
-    }
-}
-
-#[derive(Debug)]
-pub struct PathMap {
-    path_map: Vec<Vec<PathMapNode>>,
-    start: IVec2,
-}
-
-impl PathMap {
-    pub fn new(map: &[Vec<u8>], start: IVec2) -> Self {
-        let mut path_map =
-            vec![vec![PathMapNode::Path(PathMapPathNode::NotVisited); map[0].len()]; map.len()];
-
-        for y in 0..map.len() {
-            for x in 0..map[0].len() {
-                if map[y][x] == b'#' {
-                    path_map[y][x] = PathMapNode::Wall;
-                }
-            }
-        }
-
-        path_map[start.y as usize][start.x as usize] = PathMapNode::Path(PathMapPathNode::Cost(0));
-
-        Self { path_map, start }
-    }
-
-    pub fn completed(map: &[Vec<u8>], start: IVec2) -> Self {
-        let mut path_map = Self::new(map, start);
-        path_map.complete();
-        path_map
-    }
-
-    pub fn wall_at(&self, pos: IVec2) -> bool {
-        self.path_map[pos.y as usize][pos.x as usize] == PathMapNode::Wall
-    }
-
-    pub fn path_at(&self, pos: IVec2) -> Option<PathMapPathNode> {
-        self.path_map[pos.y as usize][pos.x as usize].path()
-    }
-
-    pub fn path_at_mut(&mut self, pos: IVec2) -> Option<&mut PathMapPathNode> {
-        self.path_map[pos.y as usize][pos.x as usize].path_mut()
-    }
-
-    pub fn cost_at(&self, pos: IVec2) -> Option<i32> {
-        self.path_map[pos.y as usize][pos.x as usize]
-            .path()
-            .and_then(|path| path.cost())
-    }
-
-    pub fn set_cost_at(&mut self, pos: IVec2, cost: i32) {
-        if let PathMapNode::Path(c) = &mut self.path_map[pos.y as usize][pos.x as usize] {
-            *c = PathMapPathNode::Cost(cost);
-        }
-    }
-
-    pub fn complete(&mut self) {
-        std::iter::repeat(()).fold_while(VecDeque::from([self.start]), |mut queue, _| {
-            let curr = match queue.pop_front() {
-                Some(curr) => curr,
-                None => return FoldWhile::Done(queue),
-            };
-
-            [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
-                .into_iter()
-                .map(|dir| curr + dir)
-                .for_each(|pos| {
-                    if self.wall_at(pos) || self.cost_at(pos).is_some() {
-                        return;
-                    }
-
-                    self.set_cost_at(pos, self.cost_at(curr).unwrap() + 1);
-                    queue.push_back(pos);
-                });
-
-            FoldWhile::Continue(queue)
-        });
-    }
-}
-
 pub fn parse_input(input: &str) -> (Vec<&[u8]>, IVec2, IVec2) {
     let map = input
         .lines()
@@ -163,69 +48,256 @@ pub fn parse_input(input: &str) -> (Vec<&[u8]>, IVec2, IVec2) {
     (map, start, end)
 }
 
-pub fn solution_part_1(map: &[&[u8]], start: IVec2, end: IVec2, threshold: i32) -> i32 {
-    let map = map.iter().map(|&row| row.to_vec()).collect_vec();
-    let start_paths = PathMap::completed(&map, start);
-    let end_paths = PathMap::completed(&map, end);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Manhattan,
+    Chebyshev,
+}
+
+impl Metric {
+    pub fn distance(&self, a: IVec2, b: IVec2) -> i32 {
+        let delta = (a - b).abs();
 
-    let width = map[0].len();
-    let height = map.len();
+        match self {
+            Self::Manhattan => delta.x + delta.y,
+            Self::Chebyshev => delta.max_element(),
+        }
+    }
 
-    let min_cost = start_paths.cost_at(end).unwrap();
+    /// Returns the connectivity of a single step.
+    pub fn connectivity(&self) -> Connectivity {
+        match self {
+            Self::Manhattan => Connectivity::Four,
+            Self::Chebyshev => Connectivity::Eight,
+        }
+    }
 
-    (0..height)
-        .cartesian_product(0..width)
-        .map(|(y, x)| ivec2(x as i32, y as i32))
-        .filter(|&pos| start_paths.wall_at(pos))
-        .map(|pos| {
-            [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y]
-                .into_iter()
-                .map(move |dir| pos + dir)
-                .filter(|&pos| {
-                    (0..height).contains(&(pos.y as usize))
-                        && (0..width).contains(&(pos.x as usize))
-                })
-                .filter(|&pos| !start_paths.wall_at(pos))
-                .tuple_combinations()
-                .map(|(a, b)| {
-                    (start_paths.cost_at(a).unwrap() + end_paths.cost_at(b).unwrap())
-                        .min(end_paths.cost_at(a).unwrap() + start_paths.cost_at(b).unwrap())
-                        + 2
+    /// Returns the offsets within `radius`, excluding zero.
+    pub fn ball(&self, radius: i32) -> impl Iterator<Item = IVec2> + '_ {
+        (-radius..=radius)
+            .cartesian_product(-radius..=radius)
+            .map(|(y, x)| ivec2(x, y))
+            .filter(move |&offset| {
+                offset != IVec2::ZERO && self.distance(offset, IVec2::ZERO) <= radius
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cheat {
+    pub start: IVec2,
+    pub end: IVec2,
+    pub saving: i32,
+}
+
+impl Cheat {
+    /// Returns the cheat from `start` to `end` taking `len` picoseconds, or `None` if the race
+    /// cannot be finished through it, i.e. `start` cannot be reached or the exit cannot be reached
+    /// from `end`.
+    pub fn new(
+        start_paths: &DistanceField,
+        end_paths: &DistanceField,
+        start: IVec2,
+        end: IVec2,
+        len: i32,
+    ) -> Option<Self> {
+        let min_cost = start_paths.cost_at(end_paths.sources[0])?;
+
+        Some(Self {
+            start,
+            end,
+            saving: min_cost - (start_paths.cost_at(start)? + len + end_paths.cost_at(end)?),
+        })
+    }
+}
+
+/// Returns the number of cheats for each amount of time saved.
+pub fn histogram(cheats: impl Iterator<Item = Cheat>) -> BTreeMap<i32, usize> {
+    cheats
+        .map(|cheat| cheat.saving)
+        .counts()
+        .into_iter()
+        .collect()
+}
+
+/// Returns the cells a cheat from `start` can end on through walls only, with the cheat lengths.
+fn wall_ends(
+    paths: &DistanceField,
+    start: IVec2,
+    max_len: i32,
+    metric: Metric,
+) -> Vec<(IVec2, i32)> {
+    std::iter::repeat(())
+        .fold_while(
+            (
+                VecDeque::from([(start, 0)]),
+                HashSet::from([start]),
+                Vec::new(),
+            ),
+            |(mut queue, mut visited, mut ends), _| {
+                let (curr, len) = match queue.pop_front() {
+                    Some(curr) => curr,
+                    None => return FoldWhile::Done((queue, visited, ends)),
+                };
+
+                if len == max_len {
+                    return FoldWhile::Continue((queue, visited, ends));
+                }
+
+                metric
+                    .connectivity()
+                    .dirs()
+                    .iter()
+                    .map(|&dir| curr + dir)
+                    .filter(|&pos| paths.distances.contains(pos))
+                    .for_each(|pos| {
+                        if !visited.insert(pos) {
+                            return;
+                        }
+
+                        match paths.is_blocked(pos) {
+                            true => queue.push_back((pos, len + 1)),
+                            false => ends.push((pos, len + 1)),
+                        }
+                    });
+
+                FoldWhile::Continue((queue, visited, ends))
+            },
+        )
+        .into_inner()
+        .2
+}
+
+/// Returns every cheat that saves time, lasting up to `max_len` picoseconds measured by `metric`.
+/// If `is_walls_only`, cheats may only pass through walls, and their length is that of the
+/// shortest such route.
+pub fn find_cheats(
+    map: &[&[u8]],
+    start: IVec2,
+    end: IVec2,
+    max_len: i32,
+    metric: Metric,
+    is_walls_only: bool,
+) -> impl Iterator<Item = Cheat> {
+    let start_paths = DistanceField::from_map(map, b'#', [start], Connectivity::Four);
+    let end_paths = DistanceField::from_map(map, b'#', [end], Connectivity::Four);
+
+    start_paths
+        .distances
+        .positions()
+        .filter(|&pos| start_paths.cost_at(pos).is_some())
+        .collect_vec()
+        .into_iter()
+        .flat_map(move |a| {
+            let ends = match is_walls_only {
+                true => wall_ends(&start_paths, a, max_len, metric),
+                false => metric
+                    .ball(max_len)
+                    .map(|offset| a + offset)
+                    .filter(|&b| start_paths.distances.contains(b))
+                    .filter(|&b| !start_paths.is_blocked(b))
+                    .map(|b| (b, metric.distance(a, b)))
+                    .collect_vec(),
+            };
+
+            ends.into_iter()
+                .filter_map(|(b, len)| Cheat::new(&start_paths, &end_paths, a, b, len))
+                .filter(|cheat| cheat.saving > 0)
+                .collect_vec()
+        })
+}
+
+/// Returns every cheat that saves time, lasting up to `max_len` picoseconds measured by `metric`,
+/// by walking the track in order and scanning the cells within reach of each, split across
+/// `threads` threads. Assumes the map is a single track from `start`.
+pub fn scan_cheats(
+    map: &[&[u8]],
+    start: IVec2,
+    max_len: i32,
+    metric: Metric,
+    threads: usize,
+) -> Vec<Cheat> {
+    let paths = DistanceField::from_map(map, b'#', [start], Connectivity::Four);
+
+    let track = paths
+        .distances
+        .positions()
+        .filter_map(|pos| paths.cost_at(pos).map(|cost| (cost, pos)))
+        .sorted_by_key(|&(cost, _)| cost)
+        .map(|(_, pos)| pos)
+        .collect_vec();
+
+    let offsets = metric
+        .ball(max_len)
+        .map(|offset| (offset, metric.distance(offset, IVec2::ZERO)))
+        .collect_vec();
+
+    let (paths, offsets) = (&paths, &offsets);
+    let scan = move |&a: &IVec2| {
+        let cost = paths.cost_at(a).unwrap();
+
+        offsets
+            .iter()
+            .map(|&(offset, len)| (a + offset, len))
+            .filter(|&(b, _)| paths.distances.contains(b))
+            .filter_map(|(b, len)| {
+                Some(Cheat {
+                    start: a,
+                    end: b,
+                    saving: paths.cost_at(b)? - cost - len,
                 })
-                .map(|cost| min_cost - cost)
-                .max()
-                .unwrap_or(0)
+            })
+            .filter(|cheat| cheat.saving > 0)
+            .collect_vec()
+    };
+
+    std::thread::scope(|scope| {
+        track
+            .chunks(track.len().div_ceil(threads.max(1)).max(1))
+            .map(|chunk| scope.spawn(move || chunk.iter().flat_map(scan).collect_vec()))
+            .collect_vec()
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("thread should not panic"))
+            .collect()
+    })
+}
+
+/// Returns the lengths and metrics for which `scan_cheats` and `find_cheats` disagree.
+pub fn cross_check(map: &[&[u8]], start: IVec2, end: IVec2) -> Vec<(i32, Metric)> {
+    let sorted = |cheats: Vec<Cheat>| {
+        cheats
+            .into_iter()
+            .sorted_by_key(|cheat| (cheat.start.to_array(), cheat.end.to_array()))
+            .collect_vec()
+    };
+
+    [1, 2, 3, 5, 10, 20]
+        .into_iter()
+        .cartesian_product([Metric::Manhattan, Metric::Chebyshev])
+        .filter(|&(max_len, metric)| {
+            sorted(scan_cheats(map, start, max_len, metric, 4))
+                != sorted(find_cheats(map, start, end, max_len, metric, false).collect())
         })
-        .filter(|&save| save >= threshold)
+        .collect()
+}
+
+fn threads() -> usize {
+    std::thread::available_parallelism().map_or(1, usize::from)
+}
+
+pub fn solution_part_1(map: &[&[u8]], start: IVec2, threshold: i32) -> i32 {
+    scan_cheats(map, start, 2, Metric::Manhattan, threads())
+        .into_iter()
+        .filter(|cheat| cheat.saving >= threshold)
         .count() as i32
 }
 
-pub fn solution_part_2(map: &[&[u8]], start: IVec2, end: IVec2, threshold: i32) -> i32 {
+pub fn solution_part_2(map: &[&[u8]], start: IVec2, threshold: i32) -> i32 {
     const CHEAT_COUNT: i32 = 20;
 
-    let map = map.iter().map(|&row| row.to_vec()).collect_vec();
-    let start_paths = PathMap::completed(&map, start);
-    let end_paths = PathMap::completed(&map, end);
-
-    let width = map[0].len();
-    let height = map.len();
-
-    let min_cost = start_paths.cost_at(end).unwrap();
-
-    (0..height)
-        .cartesian_product(0..width)
-        .map(|(y, x)| ivec2(x as i32, y as i32))
-        .filter(|&pos| !start_paths.wall_at(pos))
-        .tuple_combinations()
-        .map(|(a, b)| (a, b, (a.x - b.x).abs() + (a.y - b.y).abs()))
-        .filter(|&(_, _, cost)| cost <= CHEAT_COUNT)
-        .map(|(a, b, cost)| {
-            (start_paths.cost_at(a).unwrap() + end_paths.cost_at(b).unwrap())
-                .min(end_paths.cost_at(a).unwrap() + start_paths.cost_at(b).unwrap())
-                + cost
-        })
-        .map(|cost| min_cost - cost)
-        .filter(|&save| save >= threshold)
+    scan_cheats(map, start, CHEAT_COUNT, Metric::Manhattan, threads())
+        .into_iter()
+        .filter(|cheat| cheat.saving >= threshold)
         .count() as i32
 }
 
@@ -234,12 +306,173 @@ fn main() {
 
     let (map, start, end) = parse_input(&input);
 
+    match std::env::args().nth(1).as_deref() {
+        Some("histogram") => {
+            [
+                (
+                    "Part 1",
+                    histogram(
+                        scan_cheats(&map, start, 2, Metric::Manhattan, threads()).into_iter(),
+                    ),
+                ),
+                (
+                    "Part 2",
+                    histogram(
+                        scan_cheats(&map, start, 20, Metric::Manhattan, threads()).into_iter(),
+                    ),
+                ),
+            ]
+            .into_iter()
+            .for_each(|(part, histogram)| {
+                println!("{part} cheats:");
+                histogram.into_iter().for_each(|(saving, count)| {
+                    println!("  {count} cheats save {saving} picoseconds")
+                });
+            });
+            return;
+        }
+        Some("heatmap") => {
+            let paths = DistanceField::from_map(&map, b'#', [start], Connectivity::Four);
+            println!("{}", paths.heatmap());
+            return;
+        }
+        Some("check") => {
+            match cross_check(&map, start, end).as_slice() {
+                [] => println!("Engines agree"),
+                mismatches => mismatches.iter().for_each(|(max_len, metric)| {
+                    println!("Engines disagree for {metric:?} cheats up to {max_len}")
+                }),
+            }
+            return;
+        }
+        Some("cheats") => {
+            scan_cheats(&map, start, 20, Metric::Manhattan, threads())
+                .into_iter()
+                .filter(|cheat| cheat.saving >= threshold)
+                .for_each(|cheat| {
+                    println!(
+                        "{},{} -> {},{} saves {}",
+                        cheat.start.x, cheat.start.y, cheat.end.x, cheat.end.y, cheat.saving
+                    )
+                });
+            return;
+        }
+        _ => {}
+    }
+
     println!(
         "Part 1 solution: {}",
-        solution_part_1(&map, start, end, threshold)
+        solution_part_1(&map, start, threshold)
     );
     println!(
         "Part 2 solution: {}",
-        solution_part_2(&map, start, end, threshold)
+        solution_part_2(&map, start, threshold)
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a random single track carved through a walled `size` grid by a self-avoiding walk
+    /// from the top left corner.
+    fn random_track(seed: u64, size: IVec2) -> String {
+        let mut state = seed;
+        let mut rand = move |n: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % n
+        };
+
+        let is_inside = |pos: IVec2| pos.cmpgt(IVec2::ZERO).all() && pos.cmplt(size - 1).all();
+        let mut map = vec![vec![b'#'; size.x as usize]; size.y as usize];
+        let mut curr = IVec2::ONE;
+        map[curr.y as usize][curr.x as usize] = b'S';
+
+        loop {
+            // Only carve walls touching no other open cell, so the track never runs alongside itself
+            let nexts = Connectivity::Four
+                .dirs()
+                .iter()
+                .map(|&dir| curr + dir)
+                .filter(|&next| {
+                    is_inside(next)
+                        && map[next.y as usize][next.x as usize] == b'#'
+                        && Connectivity::Four
+                            .dirs()
+                            .iter()
+                            .map(|&dir| next + dir)
+                            .all(|pos| pos == curr || map[pos.y as usize][pos.x as usize] == b'#')
+                })
+                .collect_vec();
+
+            match nexts.as_slice() {
+                [] => break,
+                nexts => {
+                    curr = nexts[rand(nexts.len())];
+                    map[curr.y as usize][curr.x as usize] = b'.';
+                }
+            }
+        }
+
+        map[curr.y as usize][curr.x as usize] = b'E';
+        map.iter()
+            .map(|row| String::from_utf8_lossy(row))
+            .join("\n")
+    }
+
+    /// Counts the cheats saving each amount of time by comparing every pair of track cells.
+    fn pairwise(
+        map: &[&[u8]],
+        start: IVec2,
+        end: IVec2,
+        max_len: i32,
+        metric: Metric,
+    ) -> BTreeMap<i32, usize> {
+        let start_paths = DistanceField::from_map(map, b'#', [start], Connectivity::Four);
+        let end_paths = DistanceField::from_map(map, b'#', [end], Connectivity::Four);
+        let min_cost = start_paths.cost_at(end).unwrap();
+
+        start_paths
+            .distances
+            .positions()
+            .filter(|&pos| !start_paths.is_blocked(pos))
+            .collect_vec()
+            .into_iter()
+            .tuple_combinations()
+            .map(|(a, b)| (a, b, metric.distance(a, b)))
+            .filter(|&(_, _, len)| len <= max_len)
+            .map(|(a, b, len)| {
+                (start_paths.cost_at(a).unwrap() + end_paths.cost_at(b).unwrap())
+                    .min(end_paths.cost_at(a).unwrap() + start_paths.cost_at(b).unwrap())
+                    + len
+            })
+            .map(|cost| min_cost - cost)
+            .filter(|&saving| saving > 0)
+            .counts()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn engines_agree_on_random_tracks() {
+        (0..20).for_each(|seed| {
+            let input = random_track(seed, ivec2(17, 13));
+            let (map, start, end) = parse_input(&input);
+
+            assert_eq!(cross_check(&map, start, end), [], "seed {seed}");
+
+            [1, 2, 3, 5, 10, 20]
+                .into_iter()
+                .cartesian_product([Metric::Manhattan, Metric::Chebyshev])
+                .for_each(|(max_len, metric)| {
+                    assert_eq!(
+                        histogram(scan_cheats(&map, start, max_len, metric, 2).into_iter()),
+                        pairwise(&map, start, end, max_len, metric),
+                        "seed {seed}, {metric:?} cheats up to {max_len}"
+                    );
+                });
+        });
+    }
+}
@@ -1,3 +1,8 @@
+use std::collections::VecDeque;
+
+use glam::*;
+use itertools::{FoldWhile, Itertools};
+
 /// Get the advent of code input
 pub fn input(day: u8) -> String {
     dotenv::dotenv().unwrap();
@@ -19,3 +24,255 @@ pub fn input(day: u8) -> String {
 
     client.execute(request).unwrap().text().unwrap()
 }
+
+/// Connectivity of a grid cell to its neighbours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    pub fn dirs(&self) -> &'static [IVec2] {
+        const FOUR: [IVec2; 4] = [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y];
+        const EIGHT: [IVec2; 8] = [
+            IVec2::X,
+            IVec2::Y,
+            IVec2::NEG_X,
+            IVec2::NEG_Y,
+            IVec2::ONE,
+            IVec2::NEG_ONE,
+            ivec2(1, -1),
+            ivec2(-1, 1),
+        ];
+
+        match self {
+            Self::Four => &FOUR,
+            Self::Eight => &EIGHT,
+        }
+    }
+}
+
+/// A row-major grid of cells.
+#[derive(Debug, Clone)]
+pub struct Grid<T> {
+    pub cells: Vec<T>,
+    pub size: IVec2,
+}
+
+impl<T> Grid<T> {
+    pub fn new(size: IVec2, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            cells: vec![value; (size.x * size.y) as usize],
+            size,
+        }
+    }
+
+    /// Returns the grid with each cell set to `f` of its position.
+    pub fn from_fn(size: IVec2, f: impl Fn(IVec2) -> T) -> Self {
+        Self {
+            cells: (0..size.y)
+                .cartesian_product(0..size.x)
+                .map(|(y, x)| f(ivec2(x, y)))
+                .collect(),
+            size,
+        }
+    }
+
+    pub fn contains(&self, pos: IVec2) -> bool {
+        pos.cmpge(IVec2::ZERO).all() && pos.cmplt(self.size).all()
+    }
+
+    pub fn index(&self, pos: IVec2) -> usize {
+        (pos.y * self.size.x + pos.x) as usize
+    }
+
+    pub fn get(&self, pos: IVec2) -> Option<&T> {
+        match self.contains(pos) {
+            true => Some(&self.cells[self.index(pos)]),
+            false => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: IVec2) -> Option<&mut T> {
+        match self.contains(pos) {
+            true => {
+                let index = self.index(pos);
+                Some(&mut self.cells[index])
+            }
+            false => None,
+        }
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = IVec2> {
+        let size = self.size;
+
+        (0..size.y)
+            .cartesian_product(0..size.x)
+            .map(|(y, x)| ivec2(x, y))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distance {
+    Blocked,
+    Unreachable,
+    Cost(i32),
+}
+
+impl Distance {
+    pub fn cost(&self) -> Option<i32> {
+        match self {
+            Self::Cost(cost) => Some(*cost),
+            Self::Blocked | Self::Unreachable => None,
+        }
+    }
+}
+
+/// BFS distances over a grid from a set of sources
+#[derive(Debug, Clone)]
+pub struct DistanceField {
+    pub distances: Grid<Distance>,
+    pub sources: Vec<IVec2>,
+}
+
+impl DistanceField {
+    /// Computes the distances through the cells satisfying `is_passable`.
+    pub fn new(
+        size: IVec2,
+        is_passable: impl Fn(IVec2) -> bool,
+        sources: impl IntoIterator<Item = IVec2>,
+        connectivity: Connectivity,
+    ) -> Self {
+        let distances = Grid::from_fn(size, |pos| match is_passable(pos) {
+            true => Distance::Unreachable,
+            false => Distance::Blocked,
+        });
+
+        let mut field = Self {
+            distances,
+            sources: sources.into_iter().collect(),
+        };
+
+        let queue = field
+            .sources
+            .iter()
+            .copied()
+            .unique()
+            .filter(|&pos| field.at(pos) == Distance::Unreachable)
+            .collect::<VecDeque<_>>();
+
+        queue
+            .iter()
+            .for_each(|&pos| field.set(pos, Distance::Cost(0)));
+
+        std::iter::repeat(()).fold_while(queue, |mut queue, _| {
+            let curr = match queue.pop_front() {
+                Some(curr) => curr,
+                None => return FoldWhile::Done(queue),
+            };
+            let cost = field.cost_at(curr).unwrap();
+
+            connectivity
+                .dirs()
+                .iter()
+                .map(|&dir| curr + dir)
+                .for_each(|pos| {
+                    if field.at(pos) != Distance::Unreachable {
+                        return;
+                    }
+
+                    field.set(pos, Distance::Cost(cost + 1));
+                    queue.push_back(pos);
+                });
+
+            FoldWhile::Continue(queue)
+        });
+
+        field
+    }
+
+    /// Computes the distances through the cells of `map` that are not `wall`.
+    pub fn from_map(
+        map: &[impl AsRef<[u8]>],
+        wall: u8,
+        sources: impl IntoIterator<Item = IVec2>,
+        connectivity: Connectivity,
+    ) -> Self {
+        let size = ivec2(
+            map.first().map_or(0, |row| row.as_ref().len()) as i32,
+            map.len() as i32,
+        );
+
+        Self::new(
+            size,
+            |pos| map[pos.y as usize].as_ref()[pos.x as usize] != wall,
+            sources,
+            connectivity,
+        )
+    }
+
+    /// Returns the distance at `pos`, which is blocked outside the grid.
+    pub fn at(&self, pos: IVec2) -> Distance {
+        self.distances
+            .get(pos)
+            .copied()
+            .unwrap_or(Distance::Blocked)
+    }
+
+    fn set(&mut self, pos: IVec2, distance: Distance) {
+        if let Some(cell) = self.distances.get_mut(pos) {
+            *cell = distance;
+        }
+    }
+
+    pub fn cost_at(&self, pos: IVec2) -> Option<i32> {
+        self.at(pos).cost()
+    }
+
+    pub fn is_blocked(&self, pos: IVec2) -> bool {
+        self.at(pos) == Distance::Blocked
+    }
+
+    /// Returns the passable cells that cannot be reached from any source.
+    pub fn unreachable(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.distances
+            .positions()
+            .filter(|&pos| self.at(pos) == Distance::Unreachable)
+    }
+
+    pub fn max_cost(&self) -> Option<i32> {
+        self.distances.cells.iter().filter_map(Distance::cost).max()
+    }
+
+    /// Renders the field with ANSI colours, from blue near the sources to red furthest away.
+    pub fn heatmap(&self) -> String {
+        let max_cost = self.max_cost().unwrap_or(0).max(1) as f32;
+
+        self.distances
+            .positions()
+            .chunks(self.distances.size.x as usize)
+            .into_iter()
+            .map(|row| {
+                row.map(|pos| match self.at(pos) {
+                    Distance::Blocked => "\x1b[90m#".to_string(),
+                    Distance::Unreachable => "\x1b[0m ".to_string(),
+                    Distance::Cost(cost) => {
+                        let t = cost as f32 / max_cost;
+                        format!(
+                            "\x1b[38;2;{};{};{}m█",
+                            (255.0 * t) as u8,
+                            (255.0 * (1.0 - (2.0 * t - 1.0).abs())) as u8,
+                            (255.0 * (1.0 - t)) as u8,
+                        )
+                    }
+                })
+                .join("")
+                    + "\x1b[0m"
+            })
+            .join("\n")
+    }
+}